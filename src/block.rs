@@ -0,0 +1,100 @@
+//! Blocking distracting hosts in the hosts file while a pomodoro runs.
+//!
+//! The entries sit between two marker lines, so they can be found and removed again,
+//! also after a pom that was killed before it could clean up.
+
+use log::warn;
+use pom::state;
+use pom::PomError;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const BEGIN_MARKER: &str = "# rusty_pom block begin";
+const END_MARKER: &str = "# rusty_pom block end";
+
+#[cfg(windows)]
+fn hosts_file() -> PathBuf {
+    let root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+    PathBuf::from(root).join("System32\\drivers\\etc\\hosts")
+}
+
+#[cfg(not(windows))]
+fn hosts_file() -> PathBuf {
+    PathBuf::from("/etc/hosts")
+}
+
+/// Blocks its hosts for as long as it is alive.
+pub struct HostBlock {
+    path: PathBuf,
+}
+
+impl HostBlock {
+    /// Check that the hosts file can be changed and remove a block left behind.
+    pub fn prepare() -> Result<(), PomError> {
+        let path = hosts_file();
+        match OpenOptions::new().append(true).open(&path) {
+            Ok(_) => write_block(&path, &[]),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                Err(PomError::InvalidArgument(format!(
+                    "{} is not writable, --block needs elevated permissions",
+                    path.display()
+                )))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Point `hosts` to an unroutable address.
+    pub fn new(hosts: &[String]) -> Result<HostBlock, PomError> {
+        let path = hosts_file();
+        write_block(&path, hosts)?;
+        Ok(HostBlock { path })
+    }
+}
+
+impl Drop for HostBlock {
+    fn drop(&mut self) {
+        if let Err(e) = write_block(&self.path, &[]) {
+            warn!("Unable to lift the block in {}: {}", self.path.display(), e);
+            eprintln!(
+                "Warning: unable to lift the block in {} ({})",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Replace the marked block in the hosts file with entries for `hosts`.
+fn write_block(path: &Path, hosts: &[String]) -> Result<(), PomError> {
+    let text = fs::read_to_string(path)?;
+    let mut lines = Vec::new();
+    let mut in_block = false;
+    for line in text.lines() {
+        match line.trim() {
+            BEGIN_MARKER => in_block = true,
+            END_MARKER => in_block = false,
+            _ if !in_block => lines.push(line.to_string()),
+            _ => {}
+        }
+    }
+    if !hosts.is_empty() {
+        lines.push(BEGIN_MARKER.to_string());
+        for host in hosts {
+            lines.push(format!("0.0.0.0 {}", host));
+            lines.push(format!(":: {}", host));
+        }
+        lines.push(END_MARKER.to_string());
+    }
+
+    let mut new_text = lines.join("\n");
+    new_text.push('\n');
+    if new_text != text {
+        // A crash or a full disk must not leave the system hosts file cut short.
+        let permissions = fs::metadata(path)?.permissions();
+        state::write_atomically(path, &new_text)?;
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! A sound file played when a phase finishes, behind the `sound-file` feature.
+
+#[cfg(feature = "sound-file")]
+pub use self::audio::Chime;
+#[cfg(not(feature = "sound-file"))]
+pub use self::silent::Chime;
+
+#[cfg(feature = "sound-file")]
+mod audio {
+    use log::warn;
+    use pom::PomError;
+    use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    /// Plays a WAV, MP3, FLAC or Ogg Vorbis file on the default audio output.
+    pub struct Chime {
+        // Dropping the stream stops all sound, so it has to live as long as the chime.
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+        sound: Vec<u8>,
+    }
+
+    impl Chime {
+        /// Load the sound in `path`, failing early if it can't be decoded.
+        pub fn new(path: &Path) -> Result<Chime, PomError> {
+            let sound = fs::read(path)?;
+            Decoder::new(Cursor::new(sound.clone())).map_err(|e| {
+                PomError::InvalidArgument(format!("unable to play {}: {}", path.display(), e))
+            })?;
+            let (stream, handle) = OutputStream::try_default().map_err(|e| {
+                PomError::InvalidArgument(format!("unable to open the audio output: {}", e))
+            })?;
+            Ok(Chime {
+                _stream: stream,
+                handle,
+                sound,
+            })
+        }
+
+        /// Play the sound and wait for it to end, so it isn't cut off when pom exits.
+        pub fn play(&self) {
+            let played = Sink::try_new(&self.handle)
+                .map_err(|e| e.to_string())
+                .and_then(|sink| {
+                    let source =
+                        Decoder::new(Cursor::new(self.sound.clone())).map_err(|e| e.to_string())?;
+                    sink.append(source);
+                    sink.sleep_until_end();
+                    Ok(())
+                });
+            if let Err(e) = played {
+                warn!("Unable to play sound file: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "sound-file"))]
+mod silent {
+    use pom::PomError;
+    use std::path::Path;
+
+    /// Stand-in for builds without audio support.
+    pub struct Chime;
+
+    impl Chime {
+        pub fn new(_path: &Path) -> Result<Chime, PomError> {
+            Err(PomError::InvalidArgument(
+                "--sound-file needs pom built with the sound-file feature".to_string(),
+            ))
+        }
+
+        pub fn play(&self) {}
+    }
+}
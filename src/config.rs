@@ -0,0 +1,159 @@
+//! Defaults read from `config.toml` in the user configuration directory.
+//!
+//! ```toml
+//! work_minutes = 50
+//! short_break_minutes = 10
+//! long_break_minutes = 20
+//! sound = "alarm"
+//! symbol = "💻"
+//! daily_goal = 8
+//!
+//! [profiles.reading]
+//! work_minutes = 25
+//! short_break_minutes = 5
+//! ```
+
+use pom::PomError;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub work_minutes: u64,
+    pub short_break_minutes: u64,
+    pub long_break_minutes: u64,
+    /// Notification sound, see `SoundSetting`.
+    pub sound: Option<SoundSetting>,
+    /// Symbol shown in front of a new pomodoro.
+    pub symbol: Option<String>,
+    /// Pomodoros to finish each day, for the `5/8 today` progress.
+    pub daily_goal: Option<u32>,
+    /// Named sets of durations, picked with `--profile`.
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A sound name like `--sound` takes, or `true` and `false` of older config files.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum SoundSetting {
+    Enabled(bool),
+    Name(String),
+}
+
+/// Durations that replace the ones of the config file, the others stay as they are.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub work_minutes: Option<u64>,
+    pub short_break_minutes: Option<u64>,
+    pub long_break_minutes: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            sound: None,
+            symbol: None,
+            daily_goal: None,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file at `path`, a missing file gives the built-in defaults.
+    pub fn load(path: &Path) -> Result<Config, PomError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The name of the notification sound, `None` for the default.
+    pub fn sound_name(&self) -> Option<&str> {
+        match &self.sound {
+            Some(SoundSetting::Name(name)) => Some(name),
+            Some(SoundSetting::Enabled(false)) => Some("none"),
+            Some(SoundSetting::Enabled(true)) | None => None,
+        }
+    }
+
+    /// Apply the durations of the profile `name`.
+    pub fn with_profile(mut self, name: &str) -> Result<Config, PomError> {
+        let profile = self.profiles.remove(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            PomError::InvalidArgument(if known.is_empty() {
+                format!("unknown profile '{}', the config file has none", name)
+            } else {
+                format!(
+                    "unknown profile '{}', expected one of {}",
+                    name,
+                    known.join(", ")
+                )
+            })
+        })?;
+        self.work_minutes = profile.work_minutes.unwrap_or(self.work_minutes);
+        self.short_break_minutes = profile
+            .short_break_minutes
+            .unwrap_or(self.short_break_minutes);
+        self.long_break_minutes = profile
+            .long_break_minutes
+            .unwrap_or(self.long_break_minutes);
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Config {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn missing_keys_keep_the_defaults() {
+        let config = parse("work_minutes = 50\n");
+
+        assert_eq!(config.work_minutes, 50);
+        assert_eq!(config.short_break_minutes, 5);
+        assert_eq!(config.long_break_minutes, 15);
+        assert_eq!(config.sound_name(), None);
+    }
+
+    #[test]
+    fn sound_is_a_name_or_the_old_bool() {
+        assert_eq!(parse("sound = \"alarm\"\n").sound_name(), Some("alarm"));
+        assert_eq!(parse("sound = false\n").sound_name(), Some("none"));
+        assert_eq!(parse("sound = true\n").sound_name(), None);
+        assert!(toml::from_str::<Config>("sound = 3\n").is_err());
+    }
+
+    #[test]
+    fn profiles_replace_only_their_durations() {
+        let config = parse(
+            "work_minutes = 50\n\
+             short_break_minutes = 10\n\
+             [profiles.reading]\n\
+             work_minutes = 25\n",
+        );
+
+        let config = config.with_profile("reading").unwrap();
+
+        assert_eq!(config.work_minutes, 25);
+        assert_eq!(config.short_break_minutes, 10);
+    }
+
+    #[test]
+    fn unknown_profiles_are_an_error() {
+        assert!(parse("").with_profile("reading").is_err());
+    }
+}
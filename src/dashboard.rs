@@ -0,0 +1,126 @@
+//! The full-screen view of `--tui`, in place of the progress bar.
+
+use crate::i18n::{Lang, Msg};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use pom::PomError;
+use std::io::{self, Write};
+
+/// Digits 0 to 9 and the colon, five rows high.
+const GLYPHS: [[&str; 5]; 11] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    ["  █", "  █", "  █", "  █", "  █"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+    [" ", "█", " ", "█", " "],
+];
+
+/// What the dashboard shows.
+pub struct View<'a> {
+    /// Symbol, phase and position in the set, like the message of the bar.
+    pub title: &'a str,
+    pub label: Option<&'a str>,
+    pub remaining: u64,
+    pub paused: bool,
+    /// A question waiting for an answer, shown in place of `paused`.
+    pub prompt: Option<&'a str>,
+    pub completed_today: u32,
+    pub lang: Lang,
+}
+
+/// Keeps the terminal on the alternate screen for as long as it is alive.
+///
+/// Dropping it restores the terminal, also when the timer ends with an error.
+pub struct Dashboard {
+    /// The lines and the terminal size they were drawn for.
+    last_drawn: Option<(Vec<String>, (u16, u16))>,
+}
+
+impl Dashboard {
+    pub fn enter() -> Result<Dashboard, PomError> {
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Dashboard { last_drawn: None })
+    }
+
+    /// Redraw the screen if anything changed since the last time, the size of the
+    /// terminal included.
+    pub fn draw(&mut self, view: &View) -> Result<(), PomError> {
+        let lines = render(view);
+        let (width, height) = terminal::size()?;
+        if let Some((last_lines, last_size)) = &self.last_drawn {
+            if last_lines == &lines && *last_size == (width, height) {
+                return Ok(());
+            }
+        }
+
+        let top = height.saturating_sub(lines.len() as u16) / 2;
+        let mut stdout = io::stdout();
+        queue!(stdout, Clear(ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            let left = width.saturating_sub(line.chars().count() as u16) / 2;
+            queue!(stdout, MoveTo(left, top + row as u16), Print(line))?;
+        }
+        stdout.flush()?;
+        self.last_drawn = Some((lines, (width, height)));
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+    }
+}
+
+fn render(view: &View) -> Vec<String> {
+    let mut lines = vec![view.title.to_string()];
+    if let Some(label) = view.label {
+        lines.push(label.to_string());
+    }
+    lines.push(String::new());
+    lines.extend(big_time(view.remaining));
+    lines.push(String::new());
+    lines.push(match view.prompt {
+        Some(prompt) => prompt.to_string(),
+        None if view.paused => view.lang.text(Msg::Paused, &[]),
+        None => String::new(),
+    });
+    lines.push(view.lang.text(
+        Msg::CompletedToday,
+        &[("count", &view.completed_today.to_string())],
+    ));
+    lines.push(String::new());
+    lines.push(view.lang.text(
+        if view.paused {
+            Msg::KeysPaused
+        } else {
+            Msg::KeysRunning
+        },
+        &[],
+    ));
+    lines
+}
+
+/// `MM:SS` in large digits, hours are counted in minutes.
+fn big_time(seconds: u64) -> Vec<String> {
+    let text = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    (0..5)
+        .map(|row| {
+            text.chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(digit) => GLYPHS[digit as usize][row],
+                    None => GLYPHS[10][row],
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while running a pomodoro.
+#[derive(Debug)]
+pub enum PomError {
+    Io(io::Error),
+    State(serde_json::Error),
+    Config(toml::de::Error),
+    Signal(ctrlc::Error),
+    Terminal(crossterm::ErrorKind),
+    InvalidArgument(String),
+    Notification(String),
+    /// The name of the timer, `None` for the default one.
+    NothingToResume(Option<String>),
+}
+
+impl fmt::Display for PomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PomError::Io(e) => write!(f, "I/O error: {}", e),
+            PomError::State(e) => write!(f, "invalid state file: {}", e),
+            PomError::Config(e) => write!(f, "invalid config file: {}", e),
+            PomError::Signal(e) => write!(f, "cannot set signal handler: {}", e),
+            PomError::Terminal(e) => write!(f, "terminal error: {}", e),
+            PomError::InvalidArgument(msg) => write!(f, "{}", msg),
+            PomError::Notification(msg) => write!(f, "unable to show notification: {}", msg),
+            PomError::NothingToResume(None) => {
+                write!(f, "there is no interrupted pomodoro to resume")
+            }
+            PomError::NothingToResume(Some(name)) => {
+                write!(
+                    f,
+                    "there is no interrupted pomodoro named '{}' to resume",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl Error for PomError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PomError::Io(e) => Some(e),
+            PomError::State(e) => Some(e),
+            PomError::Config(e) => Some(e),
+            PomError::Signal(e) => Some(e),
+            PomError::Terminal(e) => Some(e),
+            PomError::InvalidArgument(_)
+            | PomError::Notification(_)
+            | PomError::NothingToResume(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for PomError {
+    fn from(e: io::Error) -> Self {
+        PomError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PomError {
+    fn from(e: serde_json::Error) -> Self {
+        PomError::State(e)
+    }
+}
+
+impl From<toml::de::Error> for PomError {
+    fn from(e: toml::de::Error) -> Self {
+        PomError::Config(e)
+    }
+}
+
+impl From<ctrlc::Error> for PomError {
+    fn from(e: ctrlc::Error) -> Self {
+        PomError::Signal(e)
+    }
+}
+
+impl From<crossterm::ErrorKind> for PomError {
+    fn from(e: crossterm::ErrorKind) -> Self {
+        PomError::Terminal(e)
+    }
+}
@@ -0,0 +1,36 @@
+//! The `--json-events` stream on stderr, for GUIs and status bars wrapping pom.
+//!
+//! Every line is one JSON object, e.g.
+//! `{"event":"tick","remaining":1499,"elapsed":1,"phase":"Work","label":null}`.
+
+use pom::state::Phase;
+use serde::Serialize;
+use std::io::{self, Write};
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum JsonEvent<'a> {
+    /// Sent once a second while a phase is running.
+    Tick {
+        remaining: u64,
+        elapsed: u64,
+        phase: Phase,
+        label: Option<&'a str>,
+    },
+    Finished {
+        phase: Phase,
+        label: Option<&'a str>,
+    },
+    Interrupted {
+        remaining: u64,
+        phase: Phase,
+        label: Option<&'a str>,
+    },
+}
+
+/// Write `event` as a line to stderr, a closed stderr is nobody's problem.
+pub fn emit(event: &JsonEvent) {
+    if let Ok(line) = serde_json::to_string(event) {
+        let _ = writeln!(io::stderr(), "{}", line);
+    }
+}
@@ -0,0 +1,292 @@
+//! A JSON lines file with one record per pomodoro, for other tools to consume.
+
+use crate::error::PomError;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionRecord {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub planned_seconds: u64,
+    pub actual_seconds: u64,
+    /// Also set for a skipped pomodoro, which counts as completed.
+    pub completed: bool,
+    /// Ended early with `s`.
+    #[serde(default)]
+    pub skipped: bool,
+    pub label: Option<String>,
+    /// Why an interrupted pomodoro was interrupted, if anybody said.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// What got done in a finished pomodoro, with `--note-prompt`.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl SessionRecord {
+    /// How the pomodoro ended, `completed`, `skipped` or `interrupted`.
+    pub fn status(&self) -> &'static str {
+        match (self.completed, self.skipped) {
+            (true, false) => "completed",
+            (true, true) => "skipped",
+            (false, _) => "interrupted",
+        }
+    }
+}
+
+/// Append `record` as a single line.
+///
+/// The line goes out in one write to a file opened for appending, so records of
+/// concurrent runs don't interleave.
+pub fn append(path: &Path, record: &SessionRecord) -> Result<(), PomError> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read all records, oldest first. A missing file has none.
+///
+/// A line that isn't a record, like one cut short by a crash, is skipped with a warning
+/// rather than costing the whole history.
+pub fn read(path: &Path) -> Result<Vec<SessionRecord>, PomError> {
+    let input = match File::open(path) {
+        Ok(input) => input,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut records = Vec::new();
+    for (number, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::warn!("Skipping line {} of {}: {}", number + 1, path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+/// The interruption reasons in `records` with how often each was given, most frequent first.
+pub fn interruption_reasons(records: &[SessionRecord]) -> Vec<(String, u32)> {
+    let mut counts = BTreeMap::new();
+    for reason in records.iter().filter_map(|record| record.reason.as_ref()) {
+        *counts.entry(reason.clone()).or_insert(0) += 1;
+    }
+    let mut reasons: Vec<_> = counts.into_iter().collect();
+    reasons.sort_by(|a, b| b.1.cmp(&a.1));
+    reasons
+}
+
+/// What went into one label, for `pom stats --by-label`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct LabelTotals {
+    /// `None` for the pomodoros without a label.
+    pub label: Option<String>,
+    /// Completed pomodoros.
+    pub pomodoros: u32,
+    /// Time spent, interrupted pomodoros included.
+    pub focused_seconds: u64,
+    /// Time planned for the same pomodoros, to compare estimates with what it took.
+    pub planned_seconds: u64,
+}
+
+/// Totals of `records` per label, the most time spent first.
+pub fn totals_by_label(records: &[SessionRecord]) -> Vec<LabelTotals> {
+    let mut totals: BTreeMap<Option<&str>, LabelTotals> = BTreeMap::new();
+    for record in records {
+        let label = record.label.as_deref();
+        let entry = totals.entry(label).or_insert_with(|| LabelTotals {
+            label: label.map(String::from),
+            pomodoros: 0,
+            focused_seconds: 0,
+            planned_seconds: 0,
+        });
+        if record.completed {
+            entry.pomodoros += 1;
+        }
+        entry.focused_seconds += record.actual_seconds;
+        entry.planned_seconds += record.planned_seconds;
+    }
+    let mut totals: Vec<_> = totals.into_iter().map(|(_, totals)| totals).collect();
+    totals.sort_by(|a, b| b.focused_seconds.cmp(&a.focused_seconds));
+    totals
+}
+
+/// Write `records` as CSV with a header row, for spreadsheets.
+pub fn write_csv(records: &[SessionRecord], mut output: impl Write) -> io::Result<()> {
+    writeln!(
+        output,
+        "date,start,end,planned_minutes,actual_minutes,completed,skipped,label,reason,note"
+    )?;
+    for record in records {
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{},{},{},{}",
+            record.start.format("%Y-%m-%d"),
+            record.start.format("%H:%M:%S"),
+            record.end.format("%H:%M:%S"),
+            minutes(record.planned_seconds),
+            minutes(record.actual_seconds),
+            record.completed,
+            record.skipped,
+            quoted(&record.label),
+            quoted(&record.reason),
+            quoted(&record.note),
+        )?;
+    }
+    Ok(())
+}
+
+/// A free text field in double quotes, quotes inside doubled. Empty if there is none.
+fn quoted(text: &Option<String>) -> String {
+    match text {
+        Some(text) => format!("\"{}\"", text.replace('"', "\"\"")),
+        None => String::new(),
+    }
+}
+
+/// Minutes rounded to two decimals, `25` or `12.5`.
+fn minutes(seconds: u64) -> f64 {
+    (seconds as f64 / 60.0 * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// A 25 minute pomodoro started at 9:00 on 2020-10-05 that ran for `actual_seconds`.
+    fn record(label: Option<&str>, actual_seconds: u64, completed: bool) -> SessionRecord {
+        let start = Local.ymd(2020, 10, 5).and_hms(9, 0, 0);
+        SessionRecord {
+            start,
+            end: start + chrono::Duration::seconds(actual_seconds as i64),
+            planned_seconds: 1500,
+            actual_seconds,
+            completed,
+            skipped: false,
+            label: label.map(String::from),
+            reason: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn csv_has_a_header_and_a_row_per_record() {
+        let mut interrupted = record(Some("say \"hi\""), 750, false);
+        interrupted.reason = Some("phone, again".to_string());
+        let records = vec![interrupted];
+        let mut output = Vec::new();
+
+        write_csv(&records, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "date,start,end,planned_minutes,actual_minutes,completed,skipped,label,reason,note\n\
+             2020-10-05,09:00:00,09:12:30,25,12.5,false,false,\"say \"\"hi\"\"\",\"phone, again\",\n"
+        );
+    }
+
+    #[test]
+    fn totals_are_grouped_by_label_most_time_first() {
+        let records = vec![
+            record(Some("email"), 1500, true),
+            record(None, 1500, true),
+            record(Some("report"), 1500, true),
+            record(Some("report"), 600, false),
+        ];
+
+        let totals = totals_by_label(&records);
+
+        assert_eq!(
+            totals,
+            vec![
+                LabelTotals {
+                    label: Some("report".to_string()),
+                    pomodoros: 1,
+                    focused_seconds: 2100,
+                    planned_seconds: 3000,
+                },
+                LabelTotals {
+                    label: None,
+                    pomodoros: 1,
+                    focused_seconds: 1500,
+                    planned_seconds: 1500,
+                },
+                LabelTotals {
+                    label: Some("email".to_string()),
+                    pomodoros: 1,
+                    focused_seconds: 1500,
+                    planned_seconds: 1500,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_pomodoros_count_as_completed() {
+        let mut skipped = record(Some("report"), 900, true);
+        skipped.skipped = true;
+        let records = vec![skipped];
+
+        let totals = totals_by_label(&records);
+
+        assert_eq!(records[0].status(), "skipped");
+        assert_eq!(totals[0].pomodoros, 1);
+        assert_eq!(totals[0].focused_seconds, 900);
+    }
+
+    #[test]
+    fn records_without_skipped_are_not_skipped() {
+        let line = "{\"start\":\"2020-10-05T09:00:00+02:00\",\"end\":\"2020-10-05T09:25:00+02:00\",\
+                    \"planned_seconds\":1500,\"actual_seconds\":1500,\"completed\":true,\"label\":null}";
+
+        let record: SessionRecord = serde_json::from_str(line).unwrap();
+
+        assert!(!record.skipped);
+        assert_eq!(record.status(), "completed");
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        append(&path, &record(Some("before"), 1500, true)).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"start\":\"2020-10-05T09:\n")
+            .unwrap();
+        append(&path, &record(Some("after"), 1500, true)).unwrap();
+
+        let records = read(&path).unwrap();
+
+        let labels: Vec<_> = records.iter().map(|r| r.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("before"), Some("after")]);
+    }
+
+    #[test]
+    fn empty_history_is_just_the_header() {
+        let mut output = Vec::new();
+
+        write_csv(&[], &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "date,start,end,planned_minutes,actual_minutes,completed,skipped,label,reason,note\n"
+        );
+    }
+}
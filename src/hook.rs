@@ -0,0 +1,134 @@
+//! User commands run when a phase ends, e.g. to flash a smart light.
+//!
+//! The command runs in the platform shell and learns what happened from `POM_*`
+//! environment variables.
+
+use log::warn;
+use pom::state::Phase;
+use serde::Serialize;
+use std::io;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What ended a phase.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookEvent {
+    Finished,
+    Interrupted,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Finished => "finished",
+            HookEvent::Interrupted => "interrupted",
+        }
+    }
+}
+
+/// Spawn `command` without waiting for it, a thread reaps it and logs a failure.
+///
+/// Sets `POM_EVENT`, `POM_PHASE`, `POM_DURATION` and `POM_ELAPSED` (in seconds), and
+/// `POM_LABEL` if the pomodoro has one.
+pub fn spawn(
+    command: &str,
+    event: HookEvent,
+    phase: Phase,
+    planned: Duration,
+    spent: Duration,
+    label: Option<&str>,
+) -> io::Result<()> {
+    let mut shell = shell(command);
+    shell
+        .env("POM_EVENT", event.name())
+        .env("POM_PHASE", format!("{:?}", phase))
+        .env("POM_DURATION", planned.as_secs().to_string())
+        .env("POM_ELAPSED", spent.as_secs().to_string());
+    if let Some(label) = label {
+        shell.env("POM_LABEL", label);
+    }
+    let mut child = shell.spawn()?;
+    let command = command.to_string();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => warn!("'{}' failed with {}", command, status),
+        Ok(_) => {}
+        Err(e) => warn!("Unable to wait for '{}': {}", command, e),
+    });
+    Ok(())
+}
+
+/// Runs a command every `interval` and remembers whether it last exited with success.
+pub struct PollCommand {
+    command: String,
+    interval: Duration,
+    child: Option<Child>,
+    last_run: Option<Instant>,
+    succeeded: bool,
+}
+
+impl PollCommand {
+    pub fn new(command: &str, interval: Duration) -> PollCommand {
+        PollCommand {
+            command: command.to_string(),
+            interval,
+            child: None,
+            last_run: None,
+            succeeded: false,
+        }
+    }
+
+    /// Pick up the exit status of the last run and start the next one when it's due,
+    /// without ever waiting for the command.
+    pub fn poll(&mut self) -> bool {
+        if let Some(child) = &mut self.child {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    self.succeeded = status.success();
+                    self.child = None;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Unable to wait for '{}': {}", self.command, e);
+                    self.child = None;
+                }
+            }
+        }
+        if self.child.is_none() && self.last_run.map_or(true, |t| t.elapsed() >= self.interval) {
+            self.last_run = Some(Instant::now());
+            match shell(&self.command)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .spawn()
+            {
+                Ok(child) => self.child = Some(child),
+                Err(e) => warn!("Unable to run '{}': {}", self.command, e),
+            }
+        }
+        self.succeeded
+    }
+}
+
+impl Drop for PollCommand {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
@@ -0,0 +1,41 @@
+//! Best-effort HTTP calls that report a finished or interrupted phase.
+
+use crate::hook::HookEvent;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::time::Duration;
+
+/// A slow server must not keep the next phase waiting for long.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The JSON body sent to `--webhook-url`.
+#[derive(Serialize)]
+pub struct WebhookPayload<'a> {
+    pub event: HookEvent,
+    pub label: Option<&'a str>,
+    pub planned_seconds: u64,
+    pub actual_seconds: u64,
+    pub timestamp: DateTime<Local>,
+}
+
+/// POST `body` to `url` with extra `headers`, any status other than 2xx is an error.
+pub fn post(url: &str, headers: &[(&str, &str)], body: &str) -> Result<(), ureq::Error> {
+    let mut request = ureq::post(url).timeout(TIMEOUT);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    request.send_string(body)?;
+    Ok(())
+}
+
+/// Send `payload` as JSON to `url`.
+pub fn webhook(url: &str, payload: &WebhookPayload) -> Result<(), ureq::Error> {
+    let body = serde_json::to_string(payload).expect("payload is always valid JSON");
+    post(url, &[("Content-Type", "application/json")], &body)
+}
+
+/// Publish `message` to `topic` on the ntfy server at `server`.
+pub fn ntfy(server: &str, topic: &str, title: &str, message: &str) -> Result<(), ureq::Error> {
+    let url = format!("{}/{}", server.trim_end_matches('/'), topic);
+    post(&url, &[("Title", title)], message)
+}
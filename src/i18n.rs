@@ -0,0 +1,298 @@
+//! Translations of the messages pom prints and shows, English is the fallback.
+
+use chrono::{DateTime, Datelike, Local};
+use indicatif::HumanDuration;
+use pom::state::Phase;
+use std::str::FromStr;
+use std::time::Duration;
+
+const WEEKDAYS_DE: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+
+/// Units for German durations in words, the largest first.
+const UNITS_DE: [(u64, &str, &str); 4] = [
+    (24 * 60 * 60, "Tag", "Tage"),
+    (60 * 60, "Stunde", "Stunden"),
+    (60, "Minute", "Minuten"),
+    (1, "Sekunde", "Sekunden"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    En,
+    De,
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Lang::En),
+            "de" => Ok(Lang::De),
+            _ => Err(format!("unknown language '{}'", s)),
+        }
+    }
+}
+
+/// A translatable message, `{name}` placeholders are filled in by `Lang::text`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Msg {
+    /// `{duration}`, `{phase}` and `{date}`.
+    StartingNew,
+    Continuing,
+    /// `{label}`, appended to `StartingNew` and `Continuing`.
+    ForLabel,
+    /// `{time}`.
+    FinishedAt,
+    /// `{time}`.
+    SkippedAt,
+    /// `{count}` as an ordinal, appended to `FinishedAt`.
+    NthToday,
+    /// `{count}` and `{goal}`, appended to `NthToday`.
+    GoalProgress,
+    /// `{time}` and `{remaining}`.
+    InterruptedAt,
+    /// `{reason}`, appended to `InterruptedAt`.
+    Reason,
+    PomodoroFinished,
+    /// `{count}` as an ordinal.
+    PomodoroFinishedBody,
+    BreakOver,
+    BreakOverBody,
+    /// Title of a `--micro-reminder`, its message is the body.
+    MicroReminder,
+    GoalReached,
+    /// `{goal}`.
+    GoalReachedBody,
+    /// `{duration}`.
+    PomodoroStarted,
+    /// `{time}`, when the pomodoro ends.
+    PomodoroStartedBody,
+    /// Title of the `--warmup` notification.
+    GetReady,
+    GetReadyBody,
+    /// `{remaining}`, title of the `--warn-at` notification.
+    TimeLeft,
+    TimeLeftBody,
+    SetComplete,
+    /// `{count}`, `{focused}` and `{interruptions}`.
+    SetCompleteBody,
+    /// `{days}`.
+    Streak,
+    /// `{days}`.
+    StreakBody,
+    PomodoroInterrupted,
+    PomodoroInterruptedBody,
+    BreakInterrupted,
+    BreakInterruptedBody,
+    /// Messages of the bars counting down `--delay`, `--snooze` and `--warmup`.
+    StartingIn,
+    SettlingIn,
+    GettingReady,
+    /// Cancelling the lead-in of an interrupted pomodoro.
+    CancelledStillSaved,
+    CancelledBeforeStart,
+    /// `--confirm` after a break.
+    ConfirmNext,
+    /// `--interrupt-confirm` after Ctrl-C.
+    ReallyStop,
+    AskReason,
+    AskNote,
+    /// `{ago}`, the question of `--stale-after`.
+    StaleResume,
+    /// The dashboard of `--tui`.
+    Paused,
+    /// `{count}`.
+    CompletedToday,
+    KeysRunning,
+    KeysPaused,
+}
+
+impl Lang {
+    /// The text of `msg`, with each `{name}` of `args` replaced by its value.
+    pub fn text(self, msg: Msg, args: &[(&str, &str)]) -> String {
+        let template = self.translation(msg).unwrap_or_else(|| english(msg));
+        args.iter()
+            .fold(template.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+
+    /// `n` as an ordinal number, like `3rd`.
+    pub fn ordinal(self, n: u32) -> String {
+        match self {
+            Lang::En => {
+                let suffix = match (n % 10, n % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                format!("{}{}", n, suffix)
+            }
+            Lang::De => format!("{}.", n),
+        }
+    }
+
+    /// The name of `phase`, like `Short break`.
+    pub fn phase(self, phase: Phase) -> &'static str {
+        match (self, phase) {
+            (Lang::En, _) => phase.name(),
+            (Lang::De, Phase::Work) => "Pomodoro",
+            (Lang::De, Phase::ShortBreak) => "Kurze Pause",
+            (Lang::De, Phase::LongBreak) => "Lange Pause",
+        }
+    }
+
+    /// `duration` in words, like `12 minutes`.
+    pub fn duration(self, duration: Duration) -> String {
+        match self {
+            Lang::En => HumanDuration(duration).to_string(),
+            Lang::De => {
+                // Like `HumanDuration`, a unit is only taken for at least two of it.
+                let seconds = duration.as_secs();
+                let (unit, one, many) = UNITS_DE
+                    .iter()
+                    .copied()
+                    .find(|&(unit, _, _)| seconds >= 2 * unit)
+                    .unwrap_or(UNITS_DE[3]);
+                let n = (seconds + unit / 2) / unit;
+                format!("{} {}", n, if n == 1 { one } else { many })
+            }
+        }
+    }
+
+    /// `at` with its weekday, like `Monday, 5-Oct-2020 at 09:00:00`.
+    pub fn date(self, at: DateTime<Local>) -> String {
+        match self {
+            Lang::En => at.format("%A, %v at %H:%M:%S").to_string(),
+            Lang::De => format!(
+                "{}, {} um {}",
+                WEEKDAYS_DE[at.weekday().num_days_from_monday() as usize],
+                at.format("%-d.%-m.%Y"),
+                at.format("%H:%M:%S")
+            ),
+        }
+    }
+
+    /// The text of `msg` in this language, `None` where English has to do.
+    fn translation(self, msg: Msg) -> Option<&'static str> {
+        match self {
+            Lang::En => None,
+            Lang::De => Some(match msg {
+                Msg::StartingNew => "Neu: {phase} über {duration} am {date}",
+                Msg::Continuing => "Weiter: {phase} mit {duration} {date}",
+                Msg::ForLabel => " für '{label}'",
+                Msg::FinishedAt => "Fertig um {time}",
+                Msg::SkippedAt => "Übersprungen um {time}",
+                Msg::NthToday => ", dein {count} Pomodoro heute!",
+                Msg::GoalProgress => " ({count}/{goal} heute)",
+                Msg::InterruptedAt => "Unterbrochen um {time}, {remaining} übrig.",
+                Msg::Reason => " Grund: {reason}",
+                Msg::PomodoroFinished => "Pomodoro geschafft!",
+                Msg::PomodoroFinishedBody => "Dein {count} Pomodoro heute ist geschafft.",
+                Msg::BreakOver => "Pause vorbei, zurück an die Arbeit!",
+                Msg::BreakOverBody => "Deine Pause ist zu Ende.",
+                Msg::MicroReminder => "Kurze Erinnerung",
+                Msg::GoalReached => "Tagesziel erreicht!",
+                Msg::GoalReachedBody => "{goal} Pomodoros heute, gut gemacht!",
+                Msg::PomodoroStarted => "Pomodoro gestartet — {duration}",
+                Msg::PomodoroStartedBody => "Konzentriert bis {time}.",
+                Msg::GetReady => "Gleich geht's los!",
+                Msg::GetReadyBody => "Dein Pomodoro beginnt gleich.",
+                Msg::TimeLeft => "Noch {remaining}",
+                Msg::TimeLeftBody => "Zeit, den Gedanken abzuschließen.",
+                Msg::SetComplete => "Serie geschafft!",
+                Msg::SetCompleteBody => {
+                    "Serie von {count} Pomodoros geschafft: {focused} konzentriert, \
+                     {interruptions} Unterbrechungen."
+                }
+                Msg::Streak => "{days} Tage in Folge!",
+                Msg::StreakBody => "{days} Tage in Folge mit einem Pomodoro, weiter so!",
+                Msg::PomodoroInterrupted => "Pomodoro unterbrochen",
+                Msg::PomodoroInterruptedBody => "Dein Pomodoro wurde unterbrochen.",
+                Msg::BreakInterrupted => "Pause unterbrochen",
+                Msg::BreakInterruptedBody => "Deine Pause wurde unterbrochen.",
+                Msg::StartingIn => "Start in",
+                Msg::SettlingIn => "Ankommen",
+                Msg::GettingReady => "Gleich geht's los",
+                Msg::CancelledStillSaved => {
+                    "Abgebrochen, der unterbrochene Pomodoro bleibt gespeichert."
+                }
+                Msg::CancelledBeforeStart => "Abgebrochen, bevor der Pomodoro begann.",
+                Msg::ConfirmNext => "Enter startet den nächsten Pomodoro (q beendet) ",
+                Msg::ReallyStop => "Wirklich beenden? (y/N)",
+                Msg::AskReason => "Grund der Unterbrechung? (Enter überspringt) ",
+                Msg::AskNote => "Was hast du geschafft? (Enter überspringt) ",
+                Msg::StaleResume => {
+                    "Diese Sitzung wurde vor {ago} unterbrochen — fortsetzen (r) oder neu \
+                     starten (s)? "
+                }
+                Msg::Paused => "pausiert",
+                Msg::CompletedToday => "{count} heute geschafft",
+                Msg::KeysRunning => "Leertaste Pause   s überspringen   q beenden",
+                Msg::KeysPaused => "Leertaste weiter   s überspringen   q beenden",
+            }),
+        }
+    }
+}
+
+fn english(msg: Msg) -> &'static str {
+    match msg {
+        Msg::StartingNew => "Starting new {duration} {phase} on {date}",
+        Msg::Continuing => "Continuing {duration} {phase} {date}",
+        Msg::ForLabel => " for '{label}'",
+        Msg::FinishedAt => "Finished at {time}",
+        Msg::SkippedAt => "Skipped at {time}",
+        Msg::NthToday => ", your {count} pomodoro today!",
+        Msg::GoalProgress => " ({count}/{goal} today)",
+        Msg::InterruptedAt => "Interrupted at {time} with {remaining} remaining.",
+        Msg::Reason => " Reason: {reason}",
+        Msg::PomodoroFinished => "Pomodoro finished!",
+        Msg::PomodoroFinishedBody => "Your {count} pomodoro today has finished.",
+        Msg::BreakOver => "Break over, back to work!",
+        Msg::BreakOverBody => "Your break has finished.",
+        Msg::MicroReminder => "Quick reminder",
+        Msg::GoalReached => "Daily goal reached!",
+        Msg::GoalReachedBody => "{goal} pomodoros today, well done!",
+        Msg::PomodoroStarted => "Pomodoro started — {duration}",
+        Msg::PomodoroStartedBody => "Focus until {time}.",
+        Msg::GetReady => "Get ready!",
+        Msg::GetReadyBody => "Your pomodoro is about to start.",
+        Msg::TimeLeft => "{remaining} left",
+        Msg::TimeLeftBody => "Time to wrap up your thought.",
+        Msg::SetComplete => "Set complete!",
+        Msg::SetCompleteBody => {
+            "Set of {count} pomodoros complete: {focused} focused, {interruptions} interruptions."
+        }
+        Msg::Streak => "{days} day streak!",
+        Msg::StreakBody => "{days} days in a row with a pomodoro, keep going!",
+        Msg::PomodoroInterrupted => "Pomodoro interrupted",
+        Msg::PomodoroInterruptedBody => "Your pomodoro was interrupted.",
+        Msg::BreakInterrupted => "Break interrupted",
+        Msg::BreakInterruptedBody => "Your break was interrupted.",
+        Msg::StartingIn => "Starting in",
+        Msg::SettlingIn => "Settling in",
+        Msg::GettingReady => "Get ready",
+        Msg::CancelledStillSaved => "Cancelled, the interrupted pomodoro is still saved.",
+        Msg::CancelledBeforeStart => "Cancelled before the pomodoro started.",
+        Msg::ConfirmNext => "Press Enter to start the next pomodoro (or q to quit) ",
+        Msg::ReallyStop => "Really stop? (y/N)",
+        Msg::AskReason => "Reason for interruption? (Enter to skip) ",
+        Msg::AskNote => "What did you get done? (Enter to skip) ",
+        Msg::StaleResume => "This session was interrupted {ago} ago — resume (r) or restart (s)? ",
+        Msg::Paused => "paused",
+        Msg::CompletedToday => "{count} completed today",
+        Msg::KeysRunning => "space pause   s skip   q quit",
+        Msg::KeysPaused => "space resume   s skip   q quit",
+    }
+}
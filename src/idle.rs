@@ -0,0 +1,51 @@
+//! How long the user hasn't touched keyboard or mouse, for `--pause-on-idle`.
+//!
+//! Every platform asks a different source, `None` means it isn't available.
+
+use std::time::Duration;
+
+#[cfg(windows)]
+pub fn idle_time() -> Option<Duration> {
+    use winapi::um::sysinfoapi::GetTickCount;
+    use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // Safe as `info` lives for the duration of the call and has its size set.
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(u64::from(
+        now.wrapping_sub(info.dwTime),
+    )))
+}
+
+#[cfg(target_os = "macos")]
+pub fn idle_time() -> Option<Duration> {
+    // `ioreg` reports the idle time in nanoseconds, as in `"HIDIdleTime" = 1234567`.
+    let output = std::process::Command::new("ioreg")
+        .args(&["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().find(|line| line.contains("\"HIDIdleTime\""))?;
+    let nanos = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(Duration::from_nanos(nanos))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn idle_time() -> Option<Duration> {
+    // `xprintidle` prints the X session's idle time in milliseconds.
+    let output = std::process::Command::new("xprintidle").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let millis = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_millis(millis))
+}
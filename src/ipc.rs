@@ -0,0 +1,95 @@
+//! A Unix socket a running pom answers with its current status, for status bars.
+//!
+//! Every connection gets one JSON line like `{"phase":"Work","remaining":754,...}` or
+//! `null` between phases, then the connection is closed.
+
+use pom::state::Phase;
+use pom::PomError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Status {
+    pub phase: Phase,
+    pub remaining: u64,
+    pub paused: bool,
+    pub label: Option<String>,
+}
+
+pub struct StatusServer {
+    status: Arc<Mutex<Option<Status>>>,
+    path: PathBuf,
+}
+
+impl StatusServer {
+    /// Publish the status of the running timer, `None` while no timer runs.
+    pub fn update(&self, status: Option<Status>) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+}
+
+#[cfg(unix)]
+impl StatusServer {
+    /// Listen on `path` in a background thread.
+    pub fn start(path: &Path) -> Result<StatusServer, PomError> {
+        use std::io::Write;
+        use std::os::unix::net::UnixListener;
+
+        // A socket left over from a crashed run would make bind fail.
+        if query(path).is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        let listener = UnixListener::bind(path)?;
+        let status = Arc::new(Mutex::new(None));
+
+        let thread_status = status.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().filter_map(Result::ok) {
+                let line = match thread_status.lock() {
+                    Ok(status) => serde_json::to_string(&*status).unwrap_or_default(),
+                    Err(_) => continue,
+                };
+                let _ = writeln!(stream, "{}", line);
+            }
+        });
+
+        Ok(StatusServer {
+            status,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+#[cfg(not(unix))]
+impl StatusServer {
+    pub fn start(_path: &Path) -> Result<StatusServer, PomError> {
+        Err(PomError::InvalidArgument(
+            "the status socket is only supported on Unix".to_string(),
+        ))
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Ask the pom listening on `path` for its status.
+#[cfg(unix)]
+pub fn query(path: &Path) -> Result<Option<Status>, PomError> {
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(path)?;
+    Ok(serde_json::from_reader(stream)?)
+}
+
+#[cfg(not(unix))]
+pub fn query(_path: &Path) -> Result<Option<Status>, PomError> {
+    Err(PomError::InvalidArgument(
+        "the status socket is only supported on Unix".to_string(),
+    ))
+}
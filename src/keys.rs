@@ -0,0 +1,59 @@
+//! Non-blocking keyboard input while a timer is running.
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::terminal;
+use pom::PomError;
+use std::time::Duration;
+
+/// A key press the timer loop cares about.
+#[derive(Debug, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    CtrlC,
+}
+
+/// Keeps the terminal in raw mode for as long as it is alive.
+///
+/// In raw mode Ctrl-C no longer raises SIGINT, it is reported as `Key::CtrlC` instead.
+pub struct RawMode;
+
+impl RawMode {
+    /// Enable raw mode, returns `None` if stdin is not a terminal.
+    pub fn enable() -> Option<RawMode> {
+        terminal::enable_raw_mode().ok().map(|_| RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Wait up to `timeout` for a key press.
+pub fn poll_key(timeout: Duration) -> Result<Option<Key>, PomError> {
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+    match event::read()? {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers,
+        }) if modifiers.contains(KeyModifiers::CONTROL) => Ok(Some(Key::CtrlC)),
+        Event::Key(KeyEvent {
+            code: KeyCode::Enter,
+            ..
+        }) => Ok(Some(Key::Enter)),
+        Event::Key(KeyEvent {
+            code: KeyCode::Backspace,
+            ..
+        }) => Ok(Some(Key::Backspace)),
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(c),
+            ..
+        }) => Ok(Some(Key::Char(c))),
+        _ => Ok(None),
+    }
+}
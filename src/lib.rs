@@ -0,0 +1,372 @@
+//! A simple pomodoro timer.
+//!
+//! [`Timer`] does the counting down and leaves rendering to the caller, which gets
+//! called back every 250ms with the elapsed time.
+
+pub mod error;
+pub mod history;
+pub mod metrics;
+pub mod notify;
+pub mod state;
+pub mod stats;
+pub mod tasks;
+
+pub use crate::error::PomError;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often a running timer wakes up, often enough for a fluid display and a quick
+/// reaction to an interrupt.
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often a running timer saves its remaining time, so a crash loses little.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct TimerConfig {
+    /// How long a fresh timer runs.
+    pub duration: Duration,
+    /// Ignore the remaining time of an interrupted timer and start over.
+    pub restart: bool,
+    /// Where the remaining time of an interrupted timer is kept, `None` disables persistence.
+    pub state_path: Option<PathBuf>,
+    /// Speed up time for testing, `60` runs one minute per second. `1` is real time.
+    pub time_scale: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TimerOutcome {
+    Finished,
+    /// The timer was interrupted with the given time remaining.
+    Interrupted(Duration),
+}
+
+/// Where a timer gets the time from.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+static SYSTEM_CLOCK: SystemClock = SystemClock;
+
+/// Tells a timer to stop early.
+pub trait Interrupt {
+    fn triggered(&self) -> bool;
+}
+
+/// A flag set from a signal handler, like the one for Ctrl-C.
+impl Interrupt for AtomicBool {
+    fn triggered(&self) -> bool {
+        self.load(Ordering::SeqCst)
+    }
+}
+
+pub struct Timer<'a> {
+    config: TimerConfig,
+    clock: &'a dyn Clock,
+    interrupt: &'a dyn Interrupt,
+    pause: Option<&'a AtomicBool>,
+    adjustment: Option<&'a AtomicI64>,
+    duration: Duration,
+    continued: bool,
+}
+
+impl<'a> Timer<'a> {
+    /// Create a timer that stops early once `interrupt` is triggered.
+    ///
+    /// Unless `config.restart` is set, an interrupted timer saved at `config.state_path`
+    /// is continued.
+    pub fn new(config: TimerConfig, interrupt: &'a dyn Interrupt) -> Result<Timer<'a>, PomError> {
+        let mut duration = config.duration;
+        let mut continued = false;
+
+        if let (Some(path), false) = (&config.state_path, config.restart) {
+            let saved = state::get_saved_state(path)?;
+            if saved.seconds_remaining > 0 {
+                duration = Duration::from_secs(saved.seconds_remaining);
+                continued = true;
+            }
+        }
+
+        Ok(Timer {
+            config,
+            clock: &SYSTEM_CLOCK,
+            interrupt,
+            pause: None,
+            adjustment: None,
+            duration,
+            continued,
+        })
+    }
+
+    /// Take the time from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: &'a dyn Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Hold the countdown while `pause` is set.
+    pub fn with_pause(mut self, pause: &'a AtomicBool) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// Add the seconds in `adjustment` to the duration, it may change while running.
+    pub fn with_adjustment(mut self, adjustment: &'a AtomicI64) -> Self {
+        self.adjustment = Some(adjustment);
+        self
+    }
+
+    /// The time this timer counts down, adjustments included.
+    pub fn duration(&self) -> Duration {
+        let seconds = self.adjustment.map_or(0, |a| a.load(Ordering::SeqCst));
+        if seconds >= 0 {
+            self.duration + Duration::from_secs(seconds as u64)
+        } else {
+            self.duration
+                .saturating_sub(Duration::from_secs(seconds.wrapping_neg() as u64))
+        }
+    }
+
+    /// Whether this timer continues an interrupted one.
+    pub fn is_continued(&self) -> bool {
+        self.continued
+    }
+
+    /// Run the timer to completion or until interrupted.
+    ///
+    /// `on_tick` is called every 250ms with the elapsed time, paused time excluded.
+    pub fn start(&self, mut on_tick: impl FnMut(Duration)) -> Result<TimerOutcome, PomError> {
+        let start = self.clock.now();
+        let mut paused_since: Option<Instant> = None;
+        let mut paused_total = Duration::from_secs(0);
+        let mut last_checkpoint = Duration::from_secs(0);
+
+        loop {
+            let paused = self.pause.map_or(false, |p| p.load(Ordering::SeqCst));
+            match (paused, paused_since) {
+                (true, None) => paused_since = Some(self.clock.now()),
+                (false, Some(since)) => {
+                    paused_total += self.clock.now() - since;
+                    paused_since = None;
+                }
+                _ => {}
+            }
+
+            let now = self.clock.now();
+            let elapsed = (now
+                - start
+                - paused_total
+                - paused_since.map_or(Duration::from_secs(0), |p| now - p))
+                * self.config.time_scale;
+            let duration = self.duration();
+
+            if self.interrupt.triggered() {
+                let remaining = duration.saturating_sub(elapsed);
+                self.save_remaining(remaining.as_secs())?;
+                return Ok(TimerOutcome::Interrupted(remaining));
+            }
+            if elapsed >= duration {
+                self.save_remaining(0)?;
+                return Ok(TimerOutcome::Finished);
+            }
+
+            if elapsed >= last_checkpoint + CHECKPOINT_INTERVAL {
+                self.save_remaining((duration - elapsed).as_secs())?;
+                last_checkpoint = elapsed;
+            }
+
+            on_tick(elapsed);
+
+            // The elapsed time comes from the clock, so ticks may be late without drifting.
+            if paused_since.is_some() {
+                self.clock.sleep(TICK_INTERVAL);
+            } else {
+                self.clock
+                    .sleep(TICK_INTERVAL.min((duration - elapsed) / self.config.time_scale));
+            }
+        }
+    }
+
+    fn save_remaining(&self, seconds_remaining: u64) -> Result<(), PomError> {
+        match &self.config.state_path {
+            Some(path) => state::save_remaining(path, seconds_remaining),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Helpers for the tests of this crate and the `pom` binary, not part of the API.
+#[doc(hidden)]
+pub mod testing {
+    use super::Clock;
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    /// A clock that only moves when something sleeps.
+    pub struct FakeClock {
+        start: Instant,
+        offset: Cell<Duration>,
+    }
+
+    impl FakeClock {
+        pub fn new() -> FakeClock {
+            FakeClock {
+                start: Instant::now(),
+                offset: Cell::new(Duration::from_secs(0)),
+            }
+        }
+
+        /// How far the clock has moved since it was created.
+        pub fn elapsed(&self) -> Duration {
+            self.offset.get()
+        }
+    }
+
+    impl Default for FakeClock {
+        fn default() -> FakeClock {
+            FakeClock::new()
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.start + self.offset.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.offset.set(self.offset.get() + duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::FakeClock;
+    use super::*;
+    use std::path::Path;
+
+    /// Fires once the fake clock reaches `at`.
+    struct FakeInterrupt<'a> {
+        clock: &'a FakeClock,
+        at: Duration,
+    }
+
+    impl Interrupt for FakeInterrupt<'_> {
+        fn triggered(&self) -> bool {
+            self.clock.elapsed() >= self.at
+        }
+    }
+
+    fn config(duration: Duration, state_path: &Path) -> TimerConfig {
+        TimerConfig {
+            duration,
+            restart: true,
+            state_path: Some(state_path.to_path_buf()),
+            time_scale: 1,
+        }
+    }
+
+    #[test]
+    fn runs_to_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let clock = FakeClock::new();
+        let interrupt = AtomicBool::new(false);
+        let mut ticks = Vec::new();
+
+        let timer = Timer::new(config(Duration::from_secs(3), &path), &interrupt)
+            .unwrap()
+            .with_clock(&clock);
+        let outcome = timer
+            .start(|elapsed| ticks.push(elapsed.as_secs()))
+            .unwrap();
+        ticks.dedup();
+
+        assert_eq!(outcome, TimerOutcome::Finished);
+        assert_eq!(ticks, vec![0, 1, 2]);
+        assert_eq!(clock.elapsed(), Duration::from_secs(3));
+        assert_eq!(state::get_saved_state(&path).unwrap().seconds_remaining, 0);
+    }
+
+    #[test]
+    fn interrupt_saves_the_remaining_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let clock = FakeClock::new();
+        let interrupt = FakeInterrupt {
+            clock: &clock,
+            at: Duration::from_secs(600),
+        };
+
+        let timer = Timer::new(config(Duration::from_secs(1500), &path), &interrupt)
+            .unwrap()
+            .with_clock(&clock);
+        let outcome = timer.start(|_| {}).unwrap();
+
+        assert_eq!(outcome, TimerOutcome::Interrupted(Duration::from_secs(900)));
+        assert_eq!(
+            state::get_saved_state(&path).unwrap().seconds_remaining,
+            900
+        );
+    }
+
+    #[test]
+    fn continues_an_interrupted_timer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        state::save_remaining(&path, 42).unwrap();
+        let interrupt = AtomicBool::new(false);
+
+        let mut config = config(Duration::from_secs(1500), &path);
+        config.restart = false;
+        let timer = Timer::new(config, &interrupt).unwrap();
+
+        assert!(timer.is_continued());
+        assert_eq!(timer.duration(), Duration::from_secs(42));
+    }
+
+    #[test]
+    fn paused_time_does_not_count() {
+        let clock = FakeClock::new();
+        let interrupt = AtomicBool::new(false);
+        let pause = AtomicBool::new(false);
+
+        let timer = Timer::new(
+            TimerConfig {
+                duration: Duration::from_secs(5),
+                restart: true,
+                state_path: None,
+                time_scale: 1,
+            },
+            &interrupt,
+        )
+        .unwrap()
+        .with_clock(&clock)
+        .with_pause(&pause);
+        let outcome = timer
+            .start(|_| {
+                let now = clock.elapsed();
+                let paused = now >= Duration::from_secs(1) && now < Duration::from_secs(11);
+                pause.store(paused, Ordering::SeqCst);
+            })
+            .unwrap();
+
+        assert_eq!(outcome, TimerOutcome::Finished);
+        assert_eq!(clock.elapsed(), Duration::from_secs(15));
+    }
+}
@@ -0,0 +1,84 @@
+//! Rotating the log file once it grows too large.
+
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+/// Move `path` to `path.1`, `path.1` to `path.2` and so on once it's larger than
+/// `max_size`, keeping at most `keep` old files.
+pub fn rotate(path: &Path, max_size: u64, keep: u32) -> io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > max_size => {}
+        Ok(_) => return Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+
+    let rotated = |n: u32| {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        if rotated(n).exists() {
+            fs::rename(rotated(n), rotated(n + 1))?;
+        }
+    }
+    fs::rename(path, rotated(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_logs_stay_where_they_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pomodoros.log");
+        fs::write(&path, "0123456789").unwrap();
+
+        rotate(&path, 10, 3).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "0123456789");
+        assert!(!dir.path().join("pomodoros.log.1").exists());
+    }
+
+    #[test]
+    fn large_logs_move_to_the_first_old_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pomodoros.log");
+        let old = |n: u32| dir.path().join(format!("pomodoros.log.{}", n));
+        fs::write(&path, "newest, too large").unwrap();
+        fs::write(old(1), "older").unwrap();
+        fs::write(old(2), "oldest").unwrap();
+
+        rotate(&path, 10, 2).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(old(1)).unwrap(), "newest, too large");
+        assert_eq!(fs::read_to_string(old(2)).unwrap(), "older");
+        assert!(!old(3).exists());
+    }
+
+    #[test]
+    fn keeping_none_removes_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pomodoros.log");
+        fs::write(&path, "too large").unwrap();
+
+        rotate(&path, 1, 0).unwrap();
+
+        assert!(!path.exists());
+        assert!(!dir.path().join("pomodoros.log.1").exists());
+    }
+
+    #[test]
+    fn a_missing_log_is_fine() {
+        let dir = tempfile::tempdir().unwrap();
+
+        rotate(&dir.path().join("pomodoros.log"), 1, 3).unwrap();
+    }
+}
@@ -1,202 +1,3533 @@
-extern crate winrt_notification;
+mod block;
+mod chime;
+mod config;
+mod dashboard;
+mod events;
+mod hook;
+mod http;
+mod i18n;
+mod idle;
+mod ipc;
+mod keys;
+mod logfile;
+mod paths;
+mod serve;
+mod tick;
 
-use chrono::Local;
+use crate::block::HostBlock;
+use crate::chime::Chime;
+use crate::config::Config;
+use crate::dashboard::{Dashboard, View};
+use crate::events::JsonEvent;
+use crate::hook::{HookEvent, PollCommand};
+use crate::http::WebhookPayload;
+use crate::i18n::{Lang, Msg};
+use crate::ipc::{Status, StatusServer};
+use crate::keys::{Key, RawMode};
+use crate::serve::HttpStatusServer;
+use crate::tick::Ticker;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
 use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
+use clap_generate::generate;
+use clap_generate::generators::{Bash, Elvish, Fish, PowerShell, Zsh};
+use crossterm::execute;
+use crossterm::style::{style, Color, Print};
+use crossterm::terminal::{self, Clear, ClearType};
 use humantime::format_duration;
-use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
-use log::{info, LevelFilter};
-use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::{info, warn, LevelFilter};
+use pom::history::{self, SessionRecord};
+use pom::metrics;
+use pom::notify::{self, NotificationDuration, Sound};
+use pom::state::{self, Phase, SavedState};
+use pom::stats::{Event, LogEntry, Stats};
+use pom::tasks::{self, Task};
+use pom::{Clock, PomError, SystemClock, Timer, TimerConfig, TimerOutcome};
 use std::env;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use winrt_notification::{Duration as WinRtDuration, Sound, Toast};
 
-const LOGFILE_NAME: &str = "pomodoros.log";
-const STATEFILE_NAME: &str = ".rusty_pom";
+/// Streaks that are celebrated with a notification.
+const STREAK_MILESTONES: &[u32] = &[7, 30, 100];
 
-#[derive(Serialize, Deserialize)]
-struct SavedState {
-    seconds_remaining: u64,
+/// Exit code of a session cut short with Ctrl-C, as if killed by SIGINT.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// How long the question about a stale pomodoro waits before resuming it.
+const STALE_PROMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the questions for the reason of an interruption and for a note wait for an
+/// answer.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long `--interrupt-confirm` waits for an answer before stopping after all.
+const INTERRUPT_CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often `--pause-command` is run.
+const PAUSE_COMMAND_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Asking for the idle time may start a process, so don't do it on every tick.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Symbols and characters used to draw the progress bar.
+struct BarStyle {
+    symbol: String,
+    symbol_continued: String,
+    symbol_break: String,
+    symbol_paused: String,
+    /// Shown in front of the message once `--warn-at` was reached.
+    symbol_warning: String,
+    progress_chars: String,
+    tick_chars: String,
+    tick_chars_break: String,
+    /// Bar color, `None` picks one by phase.
+    color: Option<String>,
+    /// Set when `NO_COLOR` is, disables colors altogether.
+    no_color: bool,
+    show_elapsed: bool,
+    /// Leave out all times, the bar alone shows how far along a phase is.
+    hide_eta: bool,
+    /// A user supplied template that replaces the built-in one.
+    template: Option<String>,
+}
+
+impl BarStyle {
+    fn unicode() -> BarStyle {
+        BarStyle {
+            symbol: "🍅".to_string(),
+            symbol_continued: "🍏".to_string(),
+            symbol_break: "☕".to_string(),
+            symbol_paused: "⏸".to_string(),
+            symbol_warning: "⏰".to_string(),
+            progress_chars: "██ ".to_string(),
+            tick_chars: "🔴⚪ ".to_string(),
+            tick_chars_break: "🟢⚪ ".to_string(),
+            color: None,
+            no_color: false,
+            show_elapsed: false,
+            hide_eta: false,
+            template: None,
+        }
+    }
+
+    /// For terminals without emoji or Unicode support.
+    fn ascii() -> BarStyle {
+        BarStyle {
+            symbol: "#".to_string(),
+            symbol_continued: "+".to_string(),
+            symbol_break: "~".to_string(),
+            symbol_paused: "||".to_string(),
+            symbol_warning: "!".to_string(),
+            progress_chars: "##-".to_string(),
+            tick_chars: "|- ".to_string(),
+            tick_chars_break: "~- ".to_string(),
+            color: None,
+            no_color: false,
+            show_elapsed: false,
+            hide_eta: false,
+            template: None,
+        }
+    }
+
+    /// Work and breaks look different, so it's clear at a glance which one is running.
+    fn progress_style(&self, phase: Phase) -> ProgressStyle {
+        ProgressStyle::default_bar()
+            .template(&self.template(phase))
+            .progress_chars(&self.progress_chars)
+            .tick_chars(if phase.is_break() {
+                &self.tick_chars_break
+            } else {
+                &self.tick_chars
+            })
+    }
+
+    fn template(&self, phase: Phase) -> String {
+        if let Some(template) = &self.template {
+            return template.clone();
+        }
+        let times = if self.hide_eta {
+            ""
+        } else if self.show_elapsed {
+            " [{elapsed_precise}/{duration_precise}] [{eta_precise}]"
+        } else {
+            " [{eta_precise}]"
+        };
+        if self.no_color {
+            return format!("{{msg}} {{spinner}}{} [{{wide_bar}}]", times);
+        }
+        let color = match &self.color {
+            Some(color) => color.as_str(),
+            None if phase.is_break() => "green",
+            None => "red",
+        };
+        format!(
+            "{{msg}} {{spinner}}{} [{{wide_bar:.{1}/{1}}}]",
+            times, color
+        )
+    }
+}
+
+/// Notifications, sounds and the terminal bell, everything `pom test-notify` needs.
+struct Alerts {
+    /// Show desktop notifications at all.
+    notify: bool,
+    silent: bool,
+    /// Notifications are silent between these times of day.
+    quiet_hours: Option<(NaiveTime, NaiveTime)>,
+    sound: Sound,
+    /// Played when a phase finishes, instead of the notification sound.
+    chime: Option<Chime>,
+    /// How often to ring the terminal bell when a phase finishes, 0 for never.
+    bell: u32,
+    /// How long the notification at the end of a phase stays, `None` for long after
+    /// pomodoros and short after breaks.
+    notify_duration: Option<NotificationDuration>,
+    /// Templates for the notification at the end of a pomodoro or break, see `fill_template`.
+    notify_title: Option<String>,
+    notify_body: Option<String>,
+}
+
+impl Alerts {
+    fn new(matches: &clap::ArgMatches, config: &Config) -> Result<Alerts, PomError> {
+        let silent = matches.is_present("silent");
+        let sound = match (
+            matches.value_of("sound"),
+            env_var("POM_SOUND")?,
+            config.sound_name(),
+        ) {
+            (None, Some(sound), _) => sound
+                .parse()
+                .map_err(|e| PomError::InvalidArgument(format!("POM_SOUND: {}", e)))?,
+            (None, None, Some(sound)) => sound
+                .parse()
+                .map_err(|e| PomError::InvalidArgument(format!("config sound: {}", e)))?,
+            _ => parse_arg(matches, "sound", "reminder")?,
+        };
+        Ok(Alerts {
+            notify: !matches.is_present("no-notify"),
+            silent,
+            quiet_hours: match matches.value_of("quiet-hours") {
+                Some(hours) => Some(parse_quiet_hours(hours).map_err(PomError::InvalidArgument)?),
+                None => None,
+            },
+            sound,
+            chime: match matches.value_of("sound-file") {
+                Some(path) if !silent => Some(Chime::new(Path::new(path))?),
+                _ => None,
+            },
+            bell: if matches.is_present("no-bell")
+                || !matches.is_present("bell") && notify::desktop_available()
+            {
+                0
+            } else {
+                parse_arg(matches, "bell-count", "1")?
+            },
+            notify_duration: matches
+                .value_of("notify-duration")
+                .map(|duration| duration.parse().expect("clap checked the duration")),
+            notify_title: matches.value_of("notify-title").map(String::from),
+            notify_body: matches.value_of("notify-body").map(String::from),
+        })
+    }
+
+    /// Show a desktop notification that stays for `duration`, unless they are turned off.
+    ///
+    /// A failure is only logged, and the terminal bell rings instead unless it already does.
+    fn show(&self, title: &str, body: &str, duration: NotificationDuration) {
+        if !self.notify {
+            return;
+        }
+        let notifier = notify::default_notifier(self.notification_sound(), duration);
+        if let Err(e) = notifier.show(title, body) {
+            warn!("Unable to show notification '{}': {}", title, e);
+            if self.bell == 0 {
+                if let Err(e) = ring_bell(1) {
+                    warn!("Unable to ring the bell: {}", e);
+                }
+            }
+        }
+    }
+
+    /// How long the notification at the end of `phase` stays, the end of a pomodoro is
+    /// easier to miss than that of a break unless `--notify-duration` says otherwise.
+    fn finished_duration(&self, phase: Phase) -> NotificationDuration {
+        match (self.notify_duration, phase.is_break()) {
+            (Some(duration), _) => duration,
+            (None, true) => NotificationDuration::Short,
+            (None, false) => NotificationDuration::Long,
+        }
+    }
+
+    /// Title and text of the notification at the end of `phase`, `count` pomodoros today.
+    fn finished_text(
+        &self,
+        lang: Lang,
+        phase: Phase,
+        label: Option<&str>,
+        duration: Duration,
+        count: u32,
+    ) -> (String, String) {
+        let fill =
+            |template: &str| fill_template(template, lang.phase(phase), label, duration, count);
+        let (title, body) = if phase.is_break() {
+            (Msg::BreakOver, Msg::BreakOverBody)
+        } else {
+            (Msg::PomodoroFinished, Msg::PomodoroFinishedBody)
+        };
+        (
+            self.notify_title
+                .as_deref()
+                .map_or_else(|| lang.text(title, &[]), &fill),
+            self.notify_body.as_deref().map_or_else(
+                || {
+                    let count = lang.ordinal(count);
+                    lang.text(body, &[("count", &count)])
+                },
+                &fill,
+            ),
+        )
+    }
+
+    /// Whether all sounds are off, with `--silent` or during the quiet hours.
+    fn muted(&self) -> bool {
+        let quiet = self
+            .quiet_hours
+            .map_or(false, |hours| in_quiet_hours(hours, Local::now().time()));
+        self.silent || quiet
+    }
+
+    fn notification_sound(&self) -> Sound {
+        // The sound file replaces the sound of notifications.
+        if self.muted() || self.chime.is_some() {
+            Sound::None
+        } else {
+            self.sound
+        }
+    }
+
+    /// Play the `--sound-file` unless sounds are off.
+    fn play_chime(&self) {
+        if let (Some(chime), false) = (&self.chime, self.muted()) {
+            chime.play();
+        }
+    }
+}
+
+struct PomApp<'a> {
+    arg_restart: bool,
+    arg_duration: Duration,
+    break_short: u64,
+    break_long: u64,
+    cycles_before_long_break: u32,
+    ctrl_pressed: &'a AtomicBool,
+    /// Set along with `ctrl_pressed` by SIGTERM and SIGHUP, which never wait for
+    /// `--interrupt-confirm`.
+    terminated: &'a AtomicBool,
+    saved_state: &'a SavedState,
+    /// `None` for `--once`, which leaves the state file alone.
+    state_path: Option<PathBuf>,
+    history_path: PathBuf,
+    /// Prometheus textfile with counters of all pomodoros.
+    metrics_path: Option<PathBuf>,
+    label: Option<String>,
+    time_scale: u32,
+    show_progress: bool,
+    /// Leave the bar of each phase on screen instead of clearing it.
+    keep_bar: bool,
+    /// Draw the full-screen dashboard instead of the progress bar.
+    tui: bool,
+    /// Stream JSON events to stderr instead of drawing the progress bar.
+    json_events: bool,
+    status_server: Option<StatusServer>,
+    http_status_server: Option<HttpStatusServer>,
+    summary_format: SummaryFormat,
+    alerts: Alerts,
+    /// Language of messages and notifications.
+    lang: Lang,
+    style: BarStyle,
+    notify_start: bool,
+    notify_resume: bool,
+    /// Shell command run when a phase finishes.
+    notify_command: Option<String>,
+    /// Also run `notify_command` when a phase is interrupted.
+    notify_command_on_interrupt: bool,
+    /// URL every finished or interrupted pomodoro is posted to.
+    webhook_url: Option<String>,
+    /// ntfy topic that gets a message when a phase finishes.
+    ntfy_topic: Option<String>,
+    ntfy_server: String,
+    /// Also publish to `ntfy_topic` when a phase is interrupted.
+    ntfy_on_interrupt: bool,
+    pomodoros_completed: u32,
+    /// Pomodoros finished on `completed_on`, for the "3rd pomodoro today" message.
+    completed_today: u32,
+    completed_on: Option<NaiveDate>,
+    /// Pomodoros to finish each day, shown as `5/8 today`.
+    daily_goal: Option<u32>,
+    /// Days in a row with a finished pomodoro, see `SavedState::streak`.
+    streak: u32,
+    best_streak: u32,
+    count: u32,
+    set_index: u32,
+    /// Focus time and interruptions of the set so far, for the summary at its end.
+    set_focus_seconds: u64,
+    set_interruptions: u32,
+    repeat: bool,
+    max_cycles: Option<u32>,
+    /// Ticks during pomodoros, every `tick_every` seconds.
+    ticker: Option<Ticker>,
+    tick_every: u64,
+    /// Hosts blocked during pomodoros.
+    block: Vec<String>,
+    /// Report progress to the terminal with OSC 9;4 escape codes.
+    osc_progress: bool,
+    /// Small file with the symbol and remaining time, rewritten every second.
+    status_file: Option<PathBuf>,
+    /// Pause while this command exits with success.
+    pause_command: Option<String>,
+    /// Pause while the user has been idle for this long.
+    pause_on_idle: Option<Duration>,
+    /// Warn once this much of a pomodoro is left.
+    warn_at: Option<Duration>,
+    /// Also warn when a pomodoro continues with less than `warn_at` left.
+    warn_on_resume: bool,
+    /// Notifications at a fixed cadence during pomodoros, the timer keeps running.
+    micro_reminders: Vec<MicroReminder>,
+    /// Grace period before an interrupted pomodoro continues.
+    snooze: Duration,
+    /// Recorded as the reason when the pomodoro is interrupted, instead of asking.
+    reason: Option<String>,
+    /// Ask for the reason when a pomodoro is interrupted.
+    ask_reason: bool,
+    /// Ask what got done when a pomodoro finishes.
+    note_prompt: bool,
+    /// Lead-in before each new pomodoro.
+    warmup: Duration,
+    /// Wait before the session starts, unlike `warmup` only once and without notifications.
+    delay: Duration,
+    /// Ctrl-C pauses and asks first, a second one or no answer stops the timer.
+    interrupt_confirm: bool,
+    /// Wait for Enter after each break, at most `confirm_timeout`.
+    confirm: bool,
+    confirm_timeout: Option<Duration>,
+    /// The `--from-file` plan, one pomodoro per task.
+    task_file: Option<PathBuf>,
+    tasks: Vec<Task>,
+}
+
+impl PomApp<'_> {
+    /// Run the session, `false` if it was cut short with Ctrl-C.
+    fn run(&mut self) -> Result<bool, PomError> {
+        let mut phase = Phase::Work;
+        let mut timer_duration: Duration;
+        let mut was_continued: bool = false;
+
+        if (self.saved_state.seconds_remaining > 0) && !self.arg_restart {
+            phase = self.saved_state.phase;
+            timer_duration = Duration::from_secs(self.saved_state.seconds_remaining);
+            was_continued = true;
+            self.set_index = self.saved_state.set_index;
+            self.count = self.saved_state.set_size.max(1);
+            self.set_focus_seconds = self.saved_state.set_focus_seconds;
+            self.set_interruptions = self.saved_state.set_interruptions;
+            if self.label.is_none() {
+                self.label = self.saved_state.label.clone();
+            }
+        } else {
+            timer_duration = self.work_duration();
+        }
+        if self.repeat {
+            self.count = self.max_cycles.unwrap_or(u32::MAX);
+        }
+        if !self.tasks.is_empty() {
+            self.count = self.tasks.len() as u32;
+        }
+        // The delay comes first, so the snooze and warm-up still lead right into the timer.
+        if self.delay > Duration::from_secs(0) {
+            info!("Starting in {}", format_duration(self.delay));
+            if !self.run_lead_in(self.delay, Msg::StartingIn)? {
+                if was_continued {
+                    println!("{}", self.lang.text(Msg::CancelledStillSaved, &[]));
+                } else {
+                    println!("{}", self.lang.text(Msg::CancelledBeforeStart, &[]));
+                }
+                return Ok(false);
+            }
+        }
+        if was_continued && phase == Phase::Work && self.snooze > Duration::from_secs(0) {
+            // The snooze is not saved, the interrupted pomodoro stays as it was.
+            if !self.run_lead_in(self.snooze, Msg::SettlingIn)? {
+                println!("{}", self.lang.text(Msg::CancelledStillSaved, &[]));
+                return Ok(false);
+            }
+        }
+
+        loop {
+            if let (Phase::Work, Some(task)) = (phase, self.current_task()) {
+                self.label = Some(task.label.clone());
+            }
+            let is_last = self.set_index + 1 >= self.count;
+            // The last pomodoro of a set gets the set summary instead of its own toast.
+            let completes_set = is_last && phase == Phase::Work && self.count > 1 && !self.repeat;
+            let show_toast = is_last && !completes_set || self.repeat;
+            if !was_continued && phase == Phase::Work && self.warmup > Duration::from_secs(0) {
+                if self.notify_start {
+                    self.show_notification(
+                        &self.lang.text(Msg::GetReady, &[]),
+                        &self.lang.text(Msg::GetReadyBody, &[]),
+                    );
+                }
+                if !self.run_lead_in(self.warmup, Msg::GettingReady)? {
+                    println!("{}", self.lang.text(Msg::CancelledBeforeStart, &[]));
+                    return Ok(false);
+                }
+            }
+            if !self.run_timer(phase, timer_duration, was_continued, show_toast)? {
+                if self.repeat {
+                    // Ctrl-C ends a repeat session for good, there is nothing to resume.
+                    info!("Stopped repeating after {} cycles", self.set_index);
+                    self.save_state(0, phase)?;
+                }
+                return Ok(false);
+            }
+
+            if phase.is_break() {
+                self.finish_cycle();
+                if is_last {
+                    return Ok(true);
+                }
+                if self.confirm && !self.confirm_next()? {
+                    info!("Stopped before pomodoro {}", self.set_index + 1);
+                    self.save_state(0, Phase::Work)?;
+                    return Ok(true);
+                }
+                phase = Phase::Work;
+                timer_duration = self.work_duration();
+            } else {
+                if completes_set {
+                    self.complete_set();
+                }
+                self.pomodoros_completed += 1;
+                let minutes = if self.cycles_before_long_break > 0
+                    && self.pomodoros_completed % self.cycles_before_long_break == 0
+                {
+                    phase = Phase::LongBreak;
+                    self.break_long
+                } else {
+                    phase = Phase::ShortBreak;
+                    self.break_short
+                };
+
+                if minutes > 0 {
+                    timer_duration = Duration::from_secs(minutes * 60);
+                } else if is_last {
+                    self.finish_cycle();
+                    self.save_state(0, Phase::Work)?;
+                    return Ok(true);
+                } else {
+                    self.finish_cycle();
+                    phase = Phase::Work;
+                    timer_duration = self.work_duration();
+                }
+            }
+            was_continued = false;
+
+            // A Ctrl-C between two phases, e.g. while a hook or the note prompt ran, stops
+            // the session rather than interrupting a phase that hasn't started.
+            if self.ctrl_pressed.swap(false, Ordering::SeqCst) {
+                info!("Stopped before the next {}", phase.name());
+                self.save_state(0, Phase::Work)?;
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Count down `duration` before a pomodoro, `false` on Ctrl-C.
+    ///
+    /// Nothing is saved, so cancelling leaves the state as it was.
+    fn run_lead_in(&self, duration: Duration, message: Msg) -> Result<bool, PomError> {
+        let timer = Timer::new(
+            TimerConfig {
+                duration,
+                restart: true,
+                state_path: None,
+                time_scale: self.time_scale,
+            },
+            self.ctrl_pressed,
+        )?;
+
+        let bar = if self.show_progress {
+            let bar = ProgressBar::new(duration.as_secs());
+            bar.set_style(self.style.progress_style(Phase::ShortBreak));
+            bar.set_message(&self.lang.text(message, &[]));
+            Some(bar)
+        } else {
+            None
+        };
+        let outcome = timer.start(|elapsed| {
+            if let Some(bar) = &bar {
+                bar.set_position(elapsed.as_secs());
+            }
+        })?;
+        if let Some(bar) = bar {
+            bar.finish_and_clear();
+        }
+
+        Ok(outcome == TimerOutcome::Finished)
+    }
+
+    /// Wait for Enter before the next pomodoro, `false` if the user quits instead.
+    ///
+    /// Without a terminal, and once `confirm_timeout` has passed, pom just goes on.
+    fn confirm_next(&self) -> Result<bool, PomError> {
+        if !atty::is(atty::Stream::Stdin) {
+            return Ok(true);
+        }
+        print!("{}", self.lang.text(Msg::ConfirmNext, &[]));
+        io::stdout().flush()?;
+
+        let raw_mode = match RawMode::enable() {
+            Some(raw_mode) => raw_mode,
+            None => return Ok(true),
+        };
+        let deadline = self.confirm_timeout.map(|timeout| Instant::now() + timeout);
+        let go_on = loop {
+            if self.ctrl_pressed.load(Ordering::SeqCst) {
+                break false;
+            }
+            if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                break true;
+            }
+            match keys::poll_key(Duration::from_millis(100))? {
+                Some(Key::Enter) => break true,
+                Some(Key::Char('q')) | Some(Key::CtrlC) => break false,
+                _ => {}
+            }
+        };
+        drop(raw_mode);
+        println!();
+        Ok(go_on)
+    }
+
+    /// Give a heads-up that the pomodoro is about to end.
+    fn warn(&self, remaining: Duration) {
+        info!("{} left", format_duration(remaining));
+        self.show_notification(
+            &self.lang.text(
+                Msg::TimeLeft,
+                &[("remaining", &format_duration(remaining).to_string())],
+            ),
+            &self.lang.text(Msg::TimeLeftBody, &[]),
+        );
+        if self.alerts.bell > 0 {
+            if let Err(e) = ring_bell(1) {
+                warn!("Unable to ring the bell: {}", e);
+            }
+        }
+    }
+
+    /// Show a micro reminder, like looking away from the screen.
+    fn remind(&self, reminder: &MicroReminder) {
+        info!("Reminder: {}", reminder.message);
+        self.show_notification(&self.lang.text(Msg::MicroReminder, &[]), &reminder.message);
+    }
+
+    /// Log and show how the set went.
+    fn complete_set(&self) {
+        let msg = self.lang.text(
+            Msg::SetCompleteBody,
+            &[
+                ("count", &self.count.to_string()),
+                (
+                    "focused",
+                    &format_duration(Duration::from_secs(self.set_focus_seconds)).to_string(),
+                ),
+                ("interruptions", &self.set_interruptions.to_string()),
+            ],
+        );
+        info!(
+            "{} {}",
+            LogEntry::now(Event::SetComplete, Phase::Work, self.set_focus_seconds),
+            msg
+        );
+        println!("{}", msg);
+        self.alerts.show(
+            &self.lang.text(Msg::SetComplete, &[]),
+            &msg,
+            self.alerts.finished_duration(Phase::Work),
+        );
+    }
+
+    fn finish_cycle(&mut self) {
+        self.set_index += 1;
+        if self.repeat {
+            info!("Completed cycle {}", self.set_index);
+        }
+    }
+
+    /// Show a short desktop notification unless they are turned off.
+    fn show_notification(&self, title: &str, body: &str) {
+        self.alerts.show(title, body, NotificationDuration::Short);
+    }
+
+    /// Title and text of the notification at the end of `phase`.
+    fn finished_text(&self, phase: Phase, duration: Duration) -> (String, String) {
+        self.alerts.finished_text(
+            self.lang,
+            phase,
+            self.label.as_deref(),
+            duration,
+            self.completed_today,
+        )
+    }
+
+    /// Tell `pom status` and `--serve` clients what is running.
+    fn publish_status(&self, status: Option<Status>) {
+        if let Some(server) = &self.http_status_server {
+            server.update(status.clone());
+        }
+        if let Some(server) = &self.status_server {
+            server.update(status);
+        }
+    }
+
+    /// Count a finished or skipped pomodoro and tell about a reached goal or streak, the
+    /// text goes at the end of the finish message.
+    fn count_pomodoro(&mut self) -> String {
+        let completed = self.count_completed_today();
+        let count = self.lang.ordinal(completed);
+        let mut msg = self.lang.text(Msg::NthToday, &[("count", &count)]);
+        if let Some(goal) = self.daily_goal {
+            msg += &self.lang.text(
+                Msg::GoalProgress,
+                &[
+                    ("count", &completed.to_string()),
+                    ("goal", &goal.to_string()),
+                ],
+            );
+            if completed == goal {
+                info!("Daily goal of {} reached", goal);
+                self.show_notification(
+                    &self.lang.text(Msg::GoalReached, &[]),
+                    &self
+                        .lang
+                        .text(Msg::GoalReachedBody, &[("goal", &goal.to_string())]),
+                );
+            }
+        }
+        // The first pomodoro of a day extends the streak.
+        if self.completed_today == 1 && STREAK_MILESTONES.contains(&self.streak) {
+            info!("{} day streak", self.streak);
+            let days = self.streak.to_string();
+            self.show_notification(
+                &self.lang.text(Msg::Streak, &[("days", &days)]),
+                &self.lang.text(Msg::StreakBody, &[("days", &days)]),
+            );
+        }
+        msg
+    }
+
+    /// Count a finished pomodoro, starting over after midnight.
+    fn count_completed_today(&mut self) -> u32 {
+        let today = Local::today().naive_local();
+        if self.completed_on != Some(today) {
+            self.streak = if self.completed_on == Some(today.pred()) {
+                self.streak + 1
+            } else {
+                1
+            };
+            self.best_streak = self.best_streak.max(self.streak);
+            self.completed_on = Some(today);
+            self.completed_today = 0;
+        }
+        self.completed_today += 1;
+        self.completed_today
+    }
+
+    /// Pomodoros finished today so far, without counting a new one.
+    fn completed_so_far_today(&self) -> u32 {
+        if self.completed_on == Some(Local::today().naive_local()) {
+            self.completed_today
+        } else {
+            0
+        }
+    }
+
+    fn current_task(&self) -> Option<&Task> {
+        self.tasks.get(self.set_index as usize)
+    }
+
+    fn work_duration(&self) -> Duration {
+        match self.current_task().and_then(|task| task.minutes) {
+            Some(minutes) => Duration::from_secs(minutes * 60),
+            None => self.arg_duration,
+        }
+    }
+
+    /// Record which phase of the set is running, the timer keeps the remaining time current.
+    fn save_state(&self, secs_remaining: u64, phase: Phase) -> Result<(), PomError> {
+        let state = SavedState {
+            version: state::STATE_VERSION,
+            seconds_remaining: secs_remaining,
+            phase,
+            pomodoros_completed: self.pomodoros_completed,
+            set_index: self.set_index,
+            set_size: self.count,
+            label: self.label.clone(),
+            completed_today: self.completed_today,
+            completed_on: self.completed_on,
+            streak: self.streak,
+            best_streak: self.best_streak,
+            task_file: self.task_file.clone(),
+            set_focus_seconds: self.set_focus_seconds,
+            set_interruptions: self.set_interruptions,
+            planned_seconds: self.work_duration().as_secs(),
+            saved_at: Some(Local::now()),
+        };
+        match &self.state_path {
+            Some(path) => state::save_state(path, &state),
+            None => Ok(()),
+        }
+    }
+
+    /// Count down a single phase, returning `true` if it ran to completion.
+    ///
+    /// Only the final phase of a set (`show_toast == true`) shows a toast.
+    fn run_timer(
+        &mut self,
+        phase: Phase,
+        timer_duration: Duration,
+        was_continued: bool,
+        show_toast: bool,
+    ) -> Result<bool, PomError> {
+        // Machine readable summaries keep stdout to themselves.
+        let print_messages = self.summary_format == SummaryFormat::Plain;
+        let color_messages = !self.style.no_color && atty::is(atty::Stream::Stdout);
+        let _info_and_print = |entry: &LogEntry, msg: &str| {
+            info!("{} {}", entry, msg);
+            if print_messages {
+                match message_color(entry.event).filter(|_| color_messages) {
+                    Some(color) => println!("{}", style(msg).with(color)),
+                    None => println!("{}", msg),
+                }
+            }
+        };
+
+        if !was_continued {
+            self.save_state(timer_duration.as_secs(), phase)?;
+        }
+
+        let paused = AtomicBool::new(false);
+        let adjustment = AtomicI64::new(0);
+        // With --interrupt-confirm Ctrl-C only asks, the timer stops once this is set.
+        let stop = AtomicBool::new(false);
+        let timer = Timer::new(
+            TimerConfig {
+                duration: timer_duration,
+                restart: !was_continued,
+                state_path: self.state_path.clone(),
+                time_scale: self.time_scale,
+            },
+            if self.interrupt_confirm {
+                &stop
+            } else {
+                self.ctrl_pressed
+            },
+        )?
+        .with_pause(&paused)
+        .with_adjustment(&adjustment);
+        let timer_duration = timer.duration();
+
+        let symbol = if phase.is_break() {
+            self.style.symbol_break.as_str()
+        } else if was_continued {
+            self.style.symbol_continued.as_str()
+        } else {
+            self.style.symbol.as_str()
+        };
+
+        let mut message = if self.repeat && self.max_cycles.is_none() {
+            format!(
+                "{} {} #{}",
+                symbol,
+                self.lang.phase(phase),
+                self.set_index + 1
+            )
+        } else if self.count > 1 {
+            format!(
+                "{} {} {}/{}",
+                symbol,
+                self.lang.phase(phase),
+                self.set_index + 1,
+                self.count
+            )
+        } else if phase.is_break() {
+            format!("{} {}", symbol, self.lang.phase(phase))
+        } else {
+            symbol.to_string()
+        };
+        // The dashboard shows the label on a line of its own.
+        let title = message.clone();
+        if let (Some(goal), Phase::Work) = (self.daily_goal, phase) {
+            message = format!("{} {}/{}", message, self.completed_so_far_today(), goal);
+        }
+        if let (Some(label), Phase::Work) = (&self.label, phase) {
+            message = format!("{} {}", message, label);
+        }
+
+        let bar = if self.show_progress && !self.tui && !self.json_events {
+            let bar = ProgressBar::new(timer_duration.as_secs());
+            bar.set_style(self.style.progress_style(phase));
+            bar.set_message(&message);
+            Some(bar)
+        } else {
+            None
+        };
+
+        info!(
+            "{} {} {}{}",
+            LogEntry::now(Event::Started, phase, timer_duration.as_secs()),
+            symbol,
+            self.lang.text(
+                if was_continued {
+                    Msg::Continuing
+                } else {
+                    Msg::StartingNew
+                },
+                &[
+                    ("duration", &format_duration(timer_duration).to_string()),
+                    ("phase", self.lang.phase(phase)),
+                    ("date", &self.lang.date(Local::now())),
+                ]
+            ),
+            match (&self.label, phase) {
+                (Some(label), Phase::Work) => self.lang.text(Msg::ForLabel, &[("label", label)]),
+                _ => String::new(),
+            }
+        );
+
+        if phase == Phase::Work
+            && (self.notify_start && !was_continued || self.notify_resume && was_continued)
+        {
+            let ends_at = Local::now() + chrono::Duration::seconds(timer_duration.as_secs() as i64);
+            self.show_notification(
+                &self.lang.text(
+                    Msg::PomodoroStarted,
+                    &[("duration", &format_duration(timer_duration).to_string())],
+                ),
+                &self.lang.text(
+                    Msg::PomodoroStartedBody,
+                    &[("time", &ends_at.format("%H:%M").to_string())],
+                ),
+            );
+        }
+
+        let started_at = Local::now();
+        let host_block = if phase == Phase::Work && !self.block.is_empty() {
+            Some(HostBlock::new(&self.block)?)
+        } else {
+            None
+        };
+
+        // A timer that starts out below the warning threshold only warns if asked to.
+        let warn_message = format!("{} {}", self.style.symbol_warning, message);
+        let mut warned = match self.warn_at {
+            Some(warn_at) => phase.is_break() || timer_duration <= warn_at && !self.warn_on_resume,
+            None => true,
+        };
+
+        // The timer holds while paused with space or while the user is away.
+        let mut paused_manually = false;
+        let mut away = false;
+        let mut last_idle_check = Instant::now();
+        let mut pause_command = self
+            .pause_command
+            .as_deref()
+            .map(|command| PollCommand::new(command, PAUSE_COMMAND_INTERVAL));
+        let mut held = false;
+        let mut status_file_failed = false;
+        let mut last_osc = None;
+        let mut last_second = None;
+        let mut skipped = false;
+        let mut last_status_text = String::new();
+        let mut last_size = terminal::size().ok();
+        // Since when `--interrupt-confirm` is waiting for an answer.
+        let mut confirming: Option<Instant> = None;
+        let confirm_prompt = self.lang.text(Msg::ReallyStop, &[]);
+        // How many times each micro reminder went off during this phase.
+        let mut reminded = vec![0; self.micro_reminders.len()];
+
+        let phase_label = self.label.as_deref().filter(|_| phase == Phase::Work);
+        let mut dashboard = if self.tui {
+            Some(Dashboard::enter()?)
+        } else {
+            None
+        };
+
+        // Space toggles the pause, + and - add or take off a minute, in raw mode Ctrl-C
+        // arrives as a key press.
+        let raw_mode = RawMode::enable();
+        let outcome = timer.start(|elapsed| {
+            if self.status_server.is_some() || self.http_status_server.is_some() {
+                self.publish_status(Some(Status {
+                    phase,
+                    remaining: timer.duration().saturating_sub(elapsed).as_secs(),
+                    paused: paused.load(Ordering::SeqCst),
+                    label: self.label.clone(),
+                }));
+            }
+
+            let mut confirm_changed = false;
+            if raw_mode.is_some() {
+                while let Ok(Some(key)) = keys::poll_key(Duration::from_secs(0)) {
+                    if confirming.is_some() {
+                        // Anything but yes or another Ctrl-C keeps the timer going.
+                        match key {
+                            Key::CtrlC | Key::Char('y') | Key::Char('Y') => {
+                                stop.store(true, Ordering::SeqCst)
+                            }
+                            _ => {
+                                info!("Not stopping after all");
+                                confirming = None;
+                                confirm_changed = true;
+                            }
+                        }
+                        continue;
+                    }
+                    match key {
+                        Key::CtrlC => self.ctrl_pressed.store(true, Ordering::SeqCst),
+                        Key::Char(' ') => paused_manually = !paused_manually,
+                        Key::Char('+') | Key::Char('=') => {
+                            adjustment.fetch_add(60, Ordering::SeqCst);
+                        }
+                        Key::Char('-') => {
+                            // Taking off more than is left just ends the phase.
+                            let remaining = timer.duration().saturating_sub(elapsed);
+                            let minute = remaining.as_secs().min(60) as i64;
+                            adjustment.fetch_sub(minute, Ordering::SeqCst);
+                        }
+                        Key::Char('s') => {
+                            // Ending the phase right now skips it.
+                            skipped = true;
+                            let remaining = timer.duration().saturating_sub(elapsed);
+                            adjustment.fetch_sub(remaining.as_secs() as i64, Ordering::SeqCst);
+                        }
+                        Key::Char('q') if dashboard.is_some() => {
+                            self.ctrl_pressed.store(true, Ordering::SeqCst)
+                        }
+                        _ => {}
+                    }
+                    if let Some(bar) = &bar {
+                        bar.set_length(timer.duration().as_secs());
+                    }
+                }
+            }
+
+            if let Some(idle_after) = self.pause_on_idle {
+                if last_idle_check.elapsed() >= IDLE_CHECK_INTERVAL {
+                    last_idle_check = Instant::now();
+                    if let Some(idle) = idle::idle_time() {
+                        if (idle >= idle_after) != away {
+                            away = !away;
+                            info!(
+                                "{}",
+                                if away {
+                                    "Away, pausing"
+                                } else {
+                                    "Back, continuing"
+                                }
+                            );
+                        }
+                    }
+                }
+            }
+            if let Some(path) = &self.status_file {
+                let remaining = timer.duration().saturating_sub(elapsed).as_secs();
+                let text = format!(
+                    "{} {:02}:{:02}\n",
+                    if paused.load(Ordering::SeqCst) {
+                        self.style.symbol_paused.as_str()
+                    } else {
+                        symbol
+                    },
+                    remaining / 60,
+                    remaining % 60
+                );
+                if text != last_status_text {
+                    if let Err(e) = state::write_atomically(path, &text) {
+                        if !status_file_failed {
+                            warn!("Unable to write status file {}: {}", path.display(), e);
+                            status_file_failed = true;
+                        }
+                    }
+                    last_status_text = text;
+                }
+            }
+
+            if let Some(pause_command) = &mut pause_command {
+                if pause_command.poll() != held {
+                    held = !held;
+                    info!(
+                        "{}",
+                        if held {
+                            "Pause command succeeded, pausing"
+                        } else {
+                            "Pause command failed, continuing"
+                        }
+                    );
+                }
+            }
+
+            if self.interrupt_confirm && self.terminated.load(Ordering::SeqCst) {
+                // Only Ctrl-C asks, a kill or a closed terminal stops right away.
+                stop.store(true, Ordering::SeqCst);
+            } else if self.interrupt_confirm && self.ctrl_pressed.swap(false, Ordering::SeqCst) {
+                if confirming.is_some() {
+                    stop.store(true, Ordering::SeqCst);
+                } else {
+                    confirming = Some(Instant::now());
+                    confirm_changed = true;
+                    if bar.is_none() && !self.tui {
+                        print!("{}\r\n", confirm_prompt);
+                        let _ = io::stdout().flush();
+                    }
+                }
+            }
+            if confirming.map_or(false, |since| since.elapsed() >= INTERRUPT_CONFIRM_TIMEOUT) {
+                stop.store(true, Ordering::SeqCst);
+            }
+            if stop.load(Ordering::SeqCst) {
+                // Whatever runs after the timer treats this like any other Ctrl-C.
+                self.ctrl_pressed.store(true, Ordering::SeqCst);
+            }
+
+            let now_paused = paused_manually || away || held || confirming.is_some();
+            if now_paused != paused.load(Ordering::SeqCst) || confirm_changed {
+                paused.store(now_paused, Ordering::SeqCst);
+                if let Some(bar) = &bar {
+                    bar.set_message(if confirming.is_some() {
+                        confirm_prompt.as_str()
+                    } else if now_paused {
+                        self.style.symbol_paused.as_str()
+                    } else if warned {
+                        warn_message.as_str()
+                    } else {
+                        message.as_str()
+                    });
+                }
+            }
+
+            // The timer ticks several times a second, the ticker and events only once.
+            let new_second = last_second != Some(elapsed.as_secs());
+            last_second = Some(elapsed.as_secs());
+            if self.json_events && new_second {
+                events::emit(&JsonEvent::Tick {
+                    remaining: timer.duration().saturating_sub(elapsed).as_secs(),
+                    elapsed: elapsed.as_secs(),
+                    phase,
+                    label: phase_label,
+                });
+            }
+            if let (Some(ticker), Phase::Work) = (&self.ticker, phase) {
+                if new_second
+                    && !paused.load(Ordering::SeqCst)
+                    && elapsed.as_secs() > 0
+                    && elapsed.as_secs() % self.tick_every == 0
+                {
+                    ticker.tick();
+                }
+            }
+
+            if self.osc_progress {
+                let duration = timer.duration().as_secs().max(1);
+                let percent = (elapsed.as_secs() * 100 / duration).min(100);
+                let state = if paused.load(Ordering::SeqCst) { 4 } else { 1 };
+                if last_osc != Some((state, percent)) {
+                    last_osc = Some((state, percent));
+                    let _ = osc_progress(state, percent);
+                }
+            }
+
+            let remaining = timer.duration().saturating_sub(elapsed);
+            if !warned && self.warn_at.map_or(false, |warn_at| remaining <= warn_at) {
+                warned = true;
+                self.warn(remaining);
+                if let Some(bar) = &bar {
+                    if !paused.load(Ordering::SeqCst) {
+                        bar.set_message(&warn_message);
+                    }
+                }
+            }
+
+            if phase == Phase::Work && new_second && remaining > Duration::from_secs(0) {
+                for (reminder, count) in self.micro_reminders.iter().zip(&mut reminded) {
+                    let due = elapsed.as_secs() / reminder.interval.as_secs();
+                    if due > *count {
+                        *count = due;
+                        self.remind(reminder);
+                    }
+                }
+            }
+
+            if let Some(bar) = &bar {
+                let size = terminal::size().ok();
+                if size != last_size {
+                    last_size = size;
+                    redraw_after_resize(bar);
+                }
+                if paused.load(Ordering::SeqCst) {
+                    bar.tick();
+                } else {
+                    bar.set_position(elapsed.as_secs());
+                }
+            }
+            if let Some(dashboard) = &mut dashboard {
+                let _ = dashboard.draw(&View {
+                    title: &title,
+                    label: phase_label,
+                    remaining: remaining.as_secs(),
+                    paused: paused.load(Ordering::SeqCst),
+                    prompt: confirming.map(|_| confirm_prompt.as_str()),
+                    completed_today: self.completed_today,
+                    lang: self.lang,
+                });
+            }
+        })?;
+
+        drop(dashboard);
+        drop(raw_mode);
+        drop(host_block);
+        if self.osc_progress {
+            let _ = osc_progress(0, 0);
+        }
+        let timer_duration = timer.duration();
+        if let Some(path) = &self.status_file {
+            if let Err(e) = state::write_atomically(path, "idle\n") {
+                warn!("Unable to write status file {}: {}", path.display(), e);
+            }
+        }
+        self.publish_status(None);
+        if let Some(bar) = bar {
+            match (&outcome, self.keep_bar) {
+                (_, false) => bar.finish_and_clear(),
+                (TimerOutcome::Finished, true) => bar.finish_with_message(&message),
+                // Left where it stopped, to show how far the phase got.
+                (TimerOutcome::Interrupted(_), true) => bar.abandon_with_message(&message),
+            }
+        }
+
+        let mut reason = None;
+        let (finished, spent) = match outcome {
+            TimerOutcome::Interrupted(time_remaining) => {
+                // The Ctrl-C ended this phase, whether the session goes on is up to `run`.
+                self.ctrl_pressed.store(false, Ordering::SeqCst);
+                let spent = timer_duration.saturating_sub(time_remaining);
+                if self.json_events {
+                    events::emit(&JsonEvent::Interrupted {
+                        remaining: time_remaining.as_secs(),
+                        phase,
+                        label: phase_label,
+                    });
+                }
+                if phase == Phase::Work {
+                    reason = match &self.reason {
+                        Some(reason) => Some(reason.clone()),
+                        None if self.ask_reason && !self.terminated.load(Ordering::SeqCst) => {
+                            ask_reason(self.lang, self.ctrl_pressed)
+                        }
+                        None => None,
+                    };
+                }
+                let mut msg = self.lang.text(
+                    Msg::InterruptedAt,
+                    &[
+                        ("time", &Local::now().format("%H:%M:%S").to_string()),
+                        ("remaining", &self.lang.duration(time_remaining)),
+                    ],
+                );
+                if let Some(reason) = &reason {
+                    msg += &self.lang.text(Msg::Reason, &[("reason", reason)]);
+                }
+                _info_and_print(
+                    &LogEntry::now(Event::Interrupted, phase, spent.as_secs()),
+                    &msg,
+                );
+                (false, spent)
+            }
+            TimerOutcome::Finished if skipped => {
+                if self.json_events {
+                    events::emit(&JsonEvent::Finished {
+                        phase,
+                        label: phase_label,
+                    });
+                }
+                // A skipped pomodoro counts like a finished one, towards the goal and the
+                // streak as well.
+                let mut msg = self.lang.text(
+                    Msg::SkippedAt,
+                    &[("time", &Local::now().format("%H:%M:%S").to_string())],
+                );
+                if phase == Phase::Work {
+                    msg += &self.count_pomodoro();
+                }
+                _info_and_print(
+                    &LogEntry::now(Event::Skipped, phase, timer_duration.as_secs()),
+                    &msg,
+                );
+                (true, timer_duration)
+            }
+            TimerOutcome::Finished => {
+                if self.json_events {
+                    events::emit(&JsonEvent::Finished {
+                        phase,
+                        label: phase_label,
+                    });
+                }
+                let mut msg = self.lang.text(
+                    Msg::FinishedAt,
+                    &[("time", &Local::now().format("%H:%M:%S").to_string())],
+                );
+                if phase == Phase::Work {
+                    msg += &self.count_pomodoro();
+                }
+                _info_and_print(
+                    &LogEntry::now(Event::Finished, phase, timer_duration.as_secs()),
+                    &msg,
+                );
+                (true, timer_duration)
+            }
+        };
+
+        let ended_at = Local::now();
+        // Notify before asking for a note, the answer may take a while.
+        if finished && !skipped {
+            if let Err(e) = ring_bell(self.alerts.bell) {
+                warn!("Unable to ring the bell: {}", e);
+            }
+        }
+        io::stdout().flush()?;
+
+        if finished && !skipped && show_toast {
+            let (title, text) = self.finished_text(phase, timer_duration);
+            self.alerts
+                .show(&title, &text, self.alerts.finished_duration(phase));
+        }
+        if finished && !skipped {
+            self.alerts.play_chime();
+        }
+
+        if phase == Phase::Work {
+            self.set_focus_seconds += spent.as_secs();
+            if !finished {
+                self.set_interruptions += 1;
+            }
+            if let (false, Some(path)) = (finished, &self.state_path) {
+                // The timer saved the remaining time, add the totals to it. The label may
+                // have been replaced with --label on resume.
+                let mut state = state::get_saved_state(path)?;
+                state.set_focus_seconds = self.set_focus_seconds;
+                state.set_interruptions = self.set_interruptions;
+                state.label = self.label.clone();
+                state::save_state(path, &state)?;
+            }
+            let note = if finished && !skipped && self.note_prompt {
+                ask_note(self.lang, self.ctrl_pressed)
+            } else {
+                None
+            };
+            if let Some(note) = &note {
+                info!("Note: {}", note);
+            }
+            let record = SessionRecord {
+                start: started_at,
+                end: ended_at,
+                planned_seconds: timer_duration.as_secs(),
+                actual_seconds: spent.as_secs(),
+                completed: finished,
+                skipped,
+                label: self.label.clone(),
+                reason,
+                note,
+            };
+            println!("{}", summary_line(&record, self.summary_format)?);
+            history::append(&self.history_path, &record)?;
+            if let Some(path) = &self.metrics_path {
+                if let Err(e) = metrics::record(path, finished, spent.as_secs()) {
+                    warn!("Unable to update metrics file {}: {}", path.display(), e);
+                }
+            }
+        }
+
+        let event = if finished {
+            HookEvent::Finished
+        } else {
+            HookEvent::Interrupted
+        };
+        if let Some(command) = &self.notify_command {
+            if finished || self.notify_command_on_interrupt {
+                if let Err(e) = hook::spawn(
+                    command,
+                    event,
+                    phase,
+                    timer_duration,
+                    spent,
+                    self.label.as_deref(),
+                ) {
+                    warn!("Unable to run notify command '{}': {}", command, e);
+                }
+            }
+        }
+
+        if let (Some(url), Phase::Work) = (&self.webhook_url, phase) {
+            let payload = WebhookPayload {
+                event,
+                label: self.label.as_deref(),
+                planned_seconds: timer_duration.as_secs(),
+                actual_seconds: spent.as_secs(),
+                timestamp: Local::now(),
+            };
+            match http::webhook(url, &payload) {
+                Ok(()) => info!("Posted {:?} to webhook", event),
+                Err(e) => warn!("Unable to post to webhook {}: {}", url, e),
+            }
+        }
+
+        if let Some(topic) = &self.ntfy_topic {
+            if finished || self.ntfy_on_interrupt {
+                let (title, message) = match (phase.is_break(), finished) {
+                    (false, true) => (Msg::PomodoroFinished, Msg::PomodoroFinishedBody),
+                    (true, true) => (Msg::BreakOver, Msg::BreakOverBody),
+                    (false, false) => (Msg::PomodoroInterrupted, Msg::PomodoroInterruptedBody),
+                    (true, false) => (Msg::BreakInterrupted, Msg::BreakInterruptedBody),
+                };
+                let count = self.lang.ordinal(self.completed_today);
+                if let Err(e) = http::ntfy(
+                    &self.ntfy_server,
+                    topic,
+                    &self.lang.text(title, &[]),
+                    &self.lang.text(message, &[("count", &count)]),
+                ) {
+                    warn!("Unable to publish to ntfy topic {}: {}", topic, e);
+                }
+            }
+        }
+
+        Ok(finished)
+    }
+}
+
+/// Replace `{phase}`, `{label}`, `{duration}` and `{count}` (pomodoros today) in a
+/// notification text.
+fn fill_template(
+    template: &str,
+    phase: &str,
+    label: Option<&str>,
+    duration: Duration,
+    count: u32,
+) -> String {
+    template
+        .replace("{phase}", phase)
+        .replace("{label}", label.unwrap_or(""))
+        .replace("{duration}", &format_duration(duration).to_string())
+        .replace("{count}", &count.to_string())
+}
+
+/// How the summary line printed after every pomodoro looks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SummaryFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+impl SummaryFormat {
+    const NAMES: &'static [&'static str] = &["plain", "json", "csv"];
+}
+
+impl FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "plain" => Ok(SummaryFormat::Plain),
+            "json" => Ok(SummaryFormat::Json),
+            "csv" => Ok(SummaryFormat::Csv),
+            _ => Err(format!(
+                "unknown format '{}', expected one of {}",
+                name,
+                SummaryFormat::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// One line describing a finished or interrupted pomodoro.
+///
+/// CSV columns are `start,end,planned_seconds,actual_seconds,completed,label`.
+fn summary_line(record: &SessionRecord, format: SummaryFormat) -> Result<String, PomError> {
+    Ok(match format {
+        SummaryFormat::Plain => format!(
+            "Pomodoro {}–{}: {} of {}, {}{}",
+            record.start.format("%H:%M:%S"),
+            record.end.format("%H:%M:%S"),
+            format_duration(Duration::from_secs(record.actual_seconds)),
+            format_duration(Duration::from_secs(record.planned_seconds)),
+            record.status(),
+            match &record.label {
+                Some(label) => format!(", '{}'", label),
+                None => String::new(),
+            }
+        ),
+        SummaryFormat::Json => serde_json::to_string(record)?,
+        SummaryFormat::Csv => format!(
+            "{},{},{},{},{},{}",
+            record.start.to_rfc3339(),
+            record.end.to_rfc3339(),
+            record.planned_seconds,
+            record.actual_seconds,
+            record.completed,
+            match &record.label {
+                Some(label) => format!("\"{}\"", label.replace('"', "\"\"")),
+                None => String::new(),
+            }
+        ),
+    })
+}
+
+/// Report progress to the terminal with `ESC ] 9 ; 4 ; state ; percent BEL`, shown in the
+/// tab or taskbar by terminals like Windows Terminal, ConEmu or iTerm2. State 1 is normal,
+/// 4 is paused and 0 removes the indicator.
+fn osc_progress(state: u8, percent: u64) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]9;4;{};{}\x07", state, percent)?;
+    stdout.flush()
+}
+
+/// Draw `bar` from scratch once the terminal changed its size.
+///
+/// The last line drawn at the old width may have been wrapped, indicatif would only clear
+/// its last row and leave the rest behind.
+fn redraw_after_resize(bar: &ProgressBar) {
+    let _ = execute!(io::stderr(), Print("\r"), Clear(ClearType::FromCursorDown));
+    // A new draw target forgets what was drawn before and draws at the new width.
+    bar.set_draw_target(ProgressDrawTarget::stderr());
+}
+
+/// Ask why the pomodoro was interrupted, see `ask`.
+fn ask_reason(lang: Lang, interrupt: &AtomicBool) -> Option<String> {
+    ask(&lang.text(Msg::AskReason, &[]), interrupt)
 }
 
-struct PomApp<'a> {
-    arg_restart: bool,
-    arg_duration: i32,
-    ctrl_pressed: &'a AtomicBool,
-    saved_state: &'a SavedState,
+/// Ask what got done in the finished pomodoro, see `ask`.
+fn ask_note(lang: Lang, interrupt: &AtomicBool) -> Option<String> {
+    ask(&lang.text(Msg::AskNote, &[]), interrupt)
+}
+
+/// Ask `question`, `None` without a terminal, for an empty answer and after
+/// `ANSWER_TIMEOUT`.
+///
+/// The answer is read in raw mode like the keys of a running timer, so nothing is left
+/// waiting for input once the question is over. Ctrl-C skips it and sets `interrupt`, as
+/// the signal would have.
+fn ask(question: &str, interrupt: &AtomicBool) -> Option<String> {
+    if !atty::is(atty::Stream::Stdin) {
+        return None;
+    }
+    print!("{}", question);
+    io::stdout().flush().ok()?;
+
+    let raw_mode = match RawMode::enable() {
+        Some(raw_mode) => raw_mode,
+        None => {
+            println!();
+            return None;
+        }
+    };
+    let answer = read_answer(
+        &SystemClock,
+        ANSWER_TIMEOUT,
+        keys::poll_key,
+        &mut io::stdout(),
+        interrupt,
+    );
+    drop(raw_mode);
+    println!();
+    answer.unwrap_or_else(|e| {
+        warn!("Unable to read the answer: {}", e);
+        None
+    })
+}
+
+/// Read a line key by key until Enter, echoing it to `echo`.
+///
+/// `None` for an empty line, on Ctrl-C, once `interrupt` is set and after `timeout`.
+fn read_answer(
+    clock: &dyn Clock,
+    timeout: Duration,
+    mut next_key: impl FnMut(Duration) -> Result<Option<Key>, PomError>,
+    echo: &mut dyn Write,
+    interrupt: &AtomicBool,
+) -> Result<Option<String>, PomError> {
+    let deadline = clock.now() + timeout;
+    let mut line = String::new();
+    loop {
+        let now = clock.now();
+        if now >= deadline || interrupt.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        match next_key((deadline - now).min(Duration::from_millis(100)))? {
+            Some(Key::Enter) => break,
+            Some(Key::CtrlC) => {
+                interrupt.store(true, Ordering::SeqCst);
+                return Ok(None);
+            }
+            Some(Key::Backspace) => {
+                if line.pop().is_some() {
+                    write!(echo, "\x08 \x08")?;
+                }
+            }
+            Some(Key::Char(c)) => {
+                line.push(c);
+                write!(echo, "{}", c)?;
+            }
+            None => continue,
+        }
+        echo.flush()?;
+    }
+    let line = line.trim();
+    Ok(if line.is_empty() {
+        None
+    } else {
+        Some(line.to_string())
+    })
+}
+
+/// The color of the message printed for `event`, so finished and interrupted phases stand
+/// out when a set scrolls by.
+fn message_color(event: Event) -> Option<Color> {
+    match event {
+        Event::Finished => Some(Color::Green),
+        Event::Interrupted => Some(Color::Yellow),
+        _ => None,
+    }
+}
+
+/// A notification every `interval` of a pomodoro, see `--micro-reminder`.
+struct MicroReminder {
+    interval: Duration,
+    message: String,
+}
+
+/// Parse `INTERVAL:MESSAGE`, like `20m:Look away from the screen`.
+fn parse_micro_reminder(value: &str) -> Result<MicroReminder, String> {
+    let mut parts = value.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(interval), Some(message)) if !message.trim().is_empty() => {
+            // Reminders are due every whole number of seconds.
+            match parse_duration(interval.trim())? {
+                duration if duration < Duration::from_secs(1) => Err(format!(
+                    "reminder interval '{}' is shorter than 1s",
+                    interval.trim()
+                )),
+                duration => Ok(MicroReminder {
+                    interval: duration,
+                    message: message.trim().to_string(),
+                }),
+            }
+        }
+        _ => Err(format!(
+            "invalid reminder '{}', expected INTERVAL:MESSAGE like 20m:Look away",
+            value
+        )),
+    }
+}
+
+/// What to do with an interrupted pomodoro that has gone stale.
+enum StaleChoice {
+    Resume,
+    Restart,
+    Quit,
+}
+
+/// Ask whether to resume a pomodoro interrupted `ago`, resuming without a terminal and
+/// after `STALE_PROMPT_TIMEOUT`.
+fn ask_stale_resume(lang: Lang, ago: Duration) -> Result<StaleChoice, PomError> {
+    if !atty::is(atty::Stream::Stdin) {
+        return Ok(StaleChoice::Resume);
+    }
+    print!(
+        "{}",
+        lang.text(Msg::StaleResume, &[("ago", &lang.duration(ago))])
+    );
+    io::stdout().flush()?;
+
+    let raw_mode = match RawMode::enable() {
+        Some(raw_mode) => raw_mode,
+        None => return Ok(StaleChoice::Resume),
+    };
+    let deadline = Instant::now() + STALE_PROMPT_TIMEOUT;
+    let choice = loop {
+        if Instant::now() >= deadline {
+            break StaleChoice::Resume;
+        }
+        match keys::poll_key(Duration::from_millis(100))? {
+            Some(Key::Char('r')) | Some(Key::Enter) => break StaleChoice::Resume,
+            Some(Key::Char('s')) => break StaleChoice::Restart,
+            Some(Key::Char('q')) | Some(Key::CtrlC) => break StaleChoice::Quit,
+            _ => {}
+        }
+    };
+    drop(raw_mode);
+    println!();
+    Ok(choice)
+}
+
+/// Write `times` BEL characters to stdout, a little apart so each one is heard.
+fn ring_bell(times: u32) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    for i in 0..times {
+        if i > 0 {
+            std::thread::sleep(Duration::from_millis(300));
+        }
+        stdout.write_all(b"\x07")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+/// Check that a progress bar template only uses placeholders `indicatif` knows.
+fn validate_template(value: &str) -> Result<(), String> {
+    const KEYS: &[&str] = &[
+        "msg",
+        "wide_msg",
+        "prefix",
+        "spinner",
+        "bar",
+        "wide_bar",
+        "pos",
+        "len",
+        "percent",
+        "bytes",
+        "total_bytes",
+        "bytes_per_sec",
+        "elapsed",
+        "elapsed_precise",
+        "eta",
+        "eta_precise",
+        "duration",
+        "duration_precise",
+    ];
+
+    let mut rest = value;
+    while let Some(open) = rest.find(|c: char| c == '{' || c == '}') {
+        if rest[open..].starts_with('}') {
+            return Err(format!("unmatched '}}' in template '{}'", value));
+        }
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| format!("unmatched '{{' in template '{}'", value))?;
+        let placeholder = &rest[open + 1..open + close];
+        let key = placeholder.split(':').next().unwrap_or_default();
+        if !KEYS.contains(&key) {
+            return Err(format!(
+                "unknown placeholder '{{{}}}', expected one of {}",
+                key,
+                KEYS.join(", ")
+            ));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    Ok(())
+}
+
+/// The shortest pomodoro pom agrees to run.
+const MIN_DURATION: Duration = Duration::from_secs(60);
+/// The longest duration of anything, pomodoros and breaks as well as delays and timeouts.
+const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Parse a duration like `25m`, `90s` or `1h30m`, a plain number like `25` or `1.5` counts
+/// as minutes.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let duration = match value.parse::<u64>() {
+        Ok(minutes) => minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("duration '{}' is too long", value))?,
+        Err(_) => match value.parse::<f64>() {
+            // Whole seconds are precise enough, and the bounds below catch huge values
+            // before they could overflow.
+            Ok(minutes) if minutes.is_finite() && minutes >= 0.0 => {
+                Duration::from_secs((minutes * 60.0).min(u64::MAX as f64).round() as u64)
+            }
+            _ => humantime::parse_duration(value)
+                .map_err(|e| format!("invalid duration '{}': {}", value, e))?,
+        },
+    };
+    if duration > MAX_DURATION {
+        return Err(format!(
+            "duration '{}' is longer than {}",
+            value,
+            format_duration(MAX_DURATION)
+        ));
+    }
+    Ok(duration)
+}
+
+/// Parse the length of a pomodoro like `parse_duration`, at least `MIN_DURATION`.
+fn parse_pomodoro_duration(value: &str) -> Result<Duration, String> {
+    let duration = parse_duration(value)?;
+    if duration < MIN_DURATION {
+        return Err(format!(
+            "duration '{}' must be between {} and {}",
+            value,
+            format_duration(MIN_DURATION),
+            format_duration(MAX_DURATION)
+        ));
+    }
+    Ok(duration)
+}
+
+/// Breaks are plain minutes, 0 skips them.
+fn parse_break_minutes(value: &str) -> Result<u64, String> {
+    let minutes: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid number of minutes '{}'", value))?;
+    if minutes > MAX_DURATION.as_secs() / 60 {
+        return Err(format!(
+            "break of {} minutes is longer than {}",
+            minutes,
+            format_duration(MAX_DURATION)
+        ));
+    }
+    Ok(minutes)
+}
+
+/// The time from `now` until the next `HH:MM`, today or else tomorrow.
+fn parse_until<Tz: TimeZone>(value: &str, now: DateTime<Tz>) -> Result<Duration, String> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|e| format!("invalid time '{}', expected HH:MM: {}", value, e))?;
+    // Local times skipped by a daylight saving time change don't exist, of the ones
+    // repeated the first is taken.
+    let zone = now.timezone();
+    let at = |date: NaiveDate| zone.from_local_datetime(&date.and_time(time)).earliest();
+    let today = now.date().naive_local();
+    let mut end = at(today);
+    if end.as_ref().map_or(false, |end| *end <= now) {
+        end = at(today.succ());
+    }
+    let end =
+        end.ok_or_else(|| format!("{} is skipped by a daylight saving time change", value))?;
+    let duration = (end - now).to_std().expect("end lies after now");
+    if duration < Duration::from_secs(60) {
+        return Err(format!("{} is less than a minute away", value));
+    }
+    Ok(duration)
+}
+
+/// Parse a range of the day like `22:00-07:00`, which may wrap past midnight.
+fn parse_quiet_hours(value: &str) -> Result<(NaiveTime, NaiveTime), String> {
+    let parse = |time: &str| {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|e| format!("invalid time '{}', expected HH:MM: {}", time, e))
+    };
+    let mut times = value.splitn(2, '-');
+    match (times.next(), times.next()) {
+        (Some(start), Some(end)) => Ok((parse(start)?, parse(end)?)),
+        _ => Err(format!(
+            "invalid range '{}', expected START-END like 22:00-07:00",
+            value
+        )),
+    }
+}
+
+/// Whether `time` lies within `start` (inclusive) and `end` (exclusive).
+fn in_quiet_hours((start, end): (NaiveTime, NaiveTime), time: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        start <= time || time < end
+    }
+}
+
+/// Parse a size like `1M`, `500K` or a plain number of bytes.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let (digits, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, ""),
+    };
+    let factor = match unit {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => {
+            return Err(format!(
+                "invalid size '{}', expected e.g. 500K or 1M",
+                value
+            ))
+        }
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(factor))
+        .ok_or_else(|| format!("invalid size '{}', expected e.g. 500K or 1M", value))
+}
+
+/// Writes everything to both writers, for the log file and stderr of `--verbose`.
+struct Tee<A, B>(A, B);
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
+
+/// Timer names end up in file names.
+fn validate_name(value: &str) -> Result<(), String> {
+    if value.is_empty()
+        || !value
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("only letters, digits, '-' and '_' are allowed".to_string());
+    }
+    Ok(())
+}
+
+/// A host for `--block`, anything else could add arbitrary lines to the hosts file.
+fn validate_host(value: &str) -> Result<(), String> {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '#') {
+        return Err(format!(
+            "invalid host '{}', whitespace and '#' are not allowed",
+            value
+        ));
+    }
+    Ok(())
+}
+
+/// `indicatif` needs at least two progress and tick characters.
+fn validate_chars(value: &str) -> Result<(), String> {
+    if value.chars().count() < 2 {
+        return Err("at least two characters are required".to_string());
+    }
+    Ok(())
+}
+
+/// Parse the value of a command line argument, falling back to `default`.
+fn parse_arg<T: FromStr>(
+    matches: &clap::ArgMatches,
+    name: &str,
+    default: &str,
+) -> Result<T, PomError> {
+    let value = matches.value_of(name).unwrap_or(default);
+    value
+        .parse()
+        .map_err(|_| PomError::InvalidArgument(format!("invalid value '{}' for --{}", value, name)))
+}
+
+/// The value of the environment variable `name`, `None` if it is unset or empty.
+fn env_var(name: &str) -> Result<Option<String>, PomError> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(PomError::InvalidArgument(format!(
+            "{} is not valid unicode",
+            name
+        ))),
+    }
+}
+
+/// Print the `last` records of the history file started on or after `since` as a table.
+fn print_history(
+    history_path: &Path,
+    last: usize,
+    since: Option<NaiveDate>,
+    json: bool,
+) -> Result<(), PomError> {
+    let mut records = history::read(history_path)?;
+    if let Some(since) = since {
+        records.retain(|record| record.start.naive_local().date() >= since);
+    }
+    let records = &records[records.len().saturating_sub(last)..];
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(records)?);
+        return Ok(());
+    }
+    let width = records
+        .iter()
+        .filter_map(|record| record.label.as_ref())
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Label".len());
+    println!(
+        "{:<16}  {:<width$}  {:>8}  {:>8}  Status",
+        "Start",
+        "Label",
+        "Planned",
+        "Actual",
+        width = width
+    );
+    for record in records {
+        println!(
+            "{:<16}  {:<width$}  {:>8}  {:>8}  {}",
+            record.start.format("%Y-%m-%d %H:%M").to_string(),
+            record.label.as_deref().unwrap_or("-"),
+            format_duration(Duration::from_secs(record.planned_seconds)).to_string(),
+            format_duration(Duration::from_secs(record.actual_seconds)).to_string(),
+            record.status(),
+            width = width
+        );
+        if let Some(note) = &record.note {
+            println!("{:<16}  {}", "", note);
+        }
+    }
+    Ok(())
+}
+
+/// Report where pom keeps its files and what the terminal and desktop support, ending with
+/// a test notification.
+fn doctor(
+    state_path: &Path,
+    log_path: &Path,
+    history_path: &Path,
+    config_path: Option<&Path>,
+) -> Result<(), PomError> {
+    let report = |what: &str, value: &str| println!("{:<16} {}", what, value);
+    let exists = |path: &Path| {
+        format!(
+            "{}{}",
+            path.display(),
+            if path.exists() {
+                ""
+            } else {
+                " (not there yet)"
+            }
+        )
+    };
+
+    report("State file", &exists(state_path));
+    report("Log file", &exists(log_path));
+    report("History file", &exists(history_path));
+    report(
+        "Config file",
+        &config_path.map_or_else(|| "unknown, no home directory".to_string(), exists),
+    );
+
+    let dir = match state_path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+    let probe = dir.join(".rusty_pom-doctor");
+    let writable = fs::write(&probe, "").and_then(|_| fs::remove_file(&probe));
+    report(
+        "Data directory",
+        &match writable {
+            Ok(()) => format!("{} is writable", dir.display()),
+            Err(e) => format!("{} is not writable: {}", dir.display(), e),
+        },
+    );
+
+    let yes_no = |yes| if yes { "yes" } else { "no" };
+    let tty = atty::is(atty::Stream::Stdout);
+    report("Terminal", yes_no(tty));
+    report(
+        "Colors",
+        if env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty()) {
+            "off, NO_COLOR is set"
+        } else if tty {
+            "on"
+        } else {
+            "off, stdout is no terminal"
+        },
+    );
+    // There is no asking a terminal about emoji, a UTF-8 locale or Windows Terminal is the
+    // best hint.
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+    let emoji =
+        locale.contains("utf-8") || locale.contains("utf8") || env::var_os("WT_SESSION").is_some();
+    report(
+        "Emoji",
+        if emoji {
+            "probably, use --ascii if the bar looks broken"
+        } else {
+            "unlikely, try --ascii"
+        },
+    );
+
+    report("Desktop", yes_no(notify::desktop_available()));
+    report(
+        "Notification",
+        &match notify::default_notifier(Sound::None, NotificationDuration::Short)
+            .show("pom doctor", "If you can read this, notifications work.")
+        {
+            Ok(()) => "sent, check that it showed up".to_string(),
+            Err(e) => format!("failed: {}", e),
+        },
+    );
+    Ok(())
 }
 
-impl PomApp<'_> {
-    fn run(&mut self) {
-        self.run_timer();
+/// Write all records of the history to `output` or stdout.
+fn export_history(history_path: &Path, json: bool, output: Option<&Path>) -> Result<(), PomError> {
+    let records = history::read(history_path)?;
+    let mut output: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    if json {
+        serde_json::to_writer_pretty(&mut output, &records)?;
+        writeln!(output)?;
+    } else {
+        history::write_csv(&records, &mut output)?;
     }
+    output.flush()?;
+    Ok(())
+}
 
-    fn save_state(secs_remaining: u64) {
-        let mut output = File::create(STATEFILE_NAME).expect("cannot create state file");
-        let state = SavedState {
-            seconds_remaining: secs_remaining,
-        };
-        write!(output, "{}", &serde_json::to_string(&state).unwrap())
-            .expect("error writing to state file");
-    }
+/// Print totals over all tagged lines of the log file.
+fn print_stats(
+    log_path: &Path,
+    history_path: &Path,
+    state: &SavedState,
+    json: bool,
+) -> Result<(), PomError> {
+    let entries: Vec<LogEntry> = match File::open(log_path) {
+        Ok(log) => BufReader::new(log)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|line| LogEntry::parse(line))
+            .collect(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let today = Local::now().naive_local().date();
+    let mut stats = Stats::from_entries(entries, today);
+    stats.current_streak = state.current_streak(today);
+    stats.best_streak = state.best_streak;
+    stats.interruption_reasons = history::interruption_reasons(&history::read(history_path)?);
 
-    fn run_timer(&self) {
-        fn _info_and_print(msg: &str) {
-            info!("{}", msg);
-            println!("{}", msg);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!("Completed today      {}", stats.completed_today);
+        println!("Completed this week  {}", stats.completed_this_week);
+        println!(
+            "Focused time         {}",
+            format_duration(Duration::from_secs(stats.focused_seconds))
+        );
+        println!("Interruptions        {}", stats.interruptions);
+        println!("Sets this week       {}", stats.sets_completed_this_week);
+        println!(
+            "Streak               {} days (best {})",
+            stats.current_streak, stats.best_streak
+        );
+        for (i, (reason, count)) in stats.interruption_reasons.iter().take(3).enumerate() {
+            println!(
+                "{}{}× {}",
+                if i == 0 {
+                    "Top reasons          "
+                } else {
+                    "                     "
+                },
+                count,
+                reason
+            );
         }
+    }
+    Ok(())
+}
 
-        let timer_duration: Duration;
-        let mut was_interrupted: bool = false;
-        let mut was_continued: bool = false;
-        let mut symbol = "🍅";
+/// Print the pomodoros and time spent per label in the history, the most time first.
+fn print_label_totals(history_path: &Path, json: bool) -> Result<(), PomError> {
+    let totals = history::totals_by_label(&history::read(history_path)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&totals)?);
+        return Ok(());
+    }
+    if totals.is_empty() {
+        println!("No pomodoros yet");
+        return Ok(());
+    }
+    for totals in totals {
+        println!(
+            "{:<24} {:>3} pomodoros  {} focused of {} planned",
+            totals.label.as_deref().unwrap_or("(no label)"),
+            totals.pomodoros,
+            format_duration(Duration::from_secs(totals.focused_seconds)),
+            format_duration(Duration::from_secs(totals.planned_seconds))
+        );
+    }
+    Ok(())
+}
 
-        if (self.saved_state.seconds_remaining > 0) && !self.arg_restart {
-            timer_duration = Duration::from_secs(self.saved_state.seconds_remaining);
-            was_continued = true;
-            symbol = "🍏";
-        } else if self.arg_duration > 0 {
-            timer_duration = Duration::from_secs(u64::try_from(self.arg_duration).unwrap() * 60)
+/// Arguments shared by a bare `pom` and the `start` and `resume` subcommands.
+fn timer_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("duration")
+            .short('d')
+            .long("duration")
+            .about("Duration like 25m, 90s or 1h30m, a plain number like 1.5 is minutes, defaults to 25")
+            .takes_value(true)
+            .validator(|value| parse_pomodoro_duration(value).map(|_| ())),
+        Arg::new("until")
+            .long("until")
+            .about("Run a single pomodoro until HH:MM, today or else tomorrow")
+            .takes_value(true)
+            .conflicts_with_all(&["duration", "count", "repeat", "max-cycles", "from-file"]),
+        Arg::new("profile")
+            .long("profile")
+            .about("Take the pomodoro and break durations from this profile of the config file")
+            .takes_value(true),
+        Arg::new("once")
+            .long("once")
+            .about("Run a throwaway timer that neither continues nor saves any state"),
+        Arg::new("restart")
+            .short('r')
+            .long("restart")
+            .about("Restart a new pomodoro"),
+        Arg::new("break-short")
+            .long("break-short")
+            .about("Short break in minutes, defaults to 5")
+            .takes_value(true)
+            .validator(|value| parse_break_minutes(value).map(|_| ())),
+        Arg::new("break-long")
+            .long("break-long")
+            .about("Long break in minutes, defaults to 15")
+            .takes_value(true)
+            .validator(|value| parse_break_minutes(value).map(|_| ())),
+        Arg::new("long-break-interval")
+            .long("long-break-interval")
+            .about("Number of pomodoros before a long break, defaults to 4")
+            .takes_value(true),
+        Arg::new("count")
+            .short('c')
+            .long("count")
+            .about("Number of pomodoros to run back-to-back, defaults to 1")
+            .takes_value(true),
+        Arg::new("repeat")
+            .long("repeat")
+            .about("Keep alternating pomodoros and breaks until Ctrl-C"),
+        Arg::new("max-cycles")
+            .long("max-cycles")
+            .about("Stop repeating after this many pomodoros")
+            .takes_value(true),
+        Arg::new("from-file")
+            .long("from-file")
+            .about("Work through the tasks in a file, one `minutes: label` or `label` per line")
+            .takes_value(true)
+            .conflicts_with_all(&["count", "repeat", "max-cycles"]),
+        Arg::new("sound-file")
+            .long("sound-file")
+            .about("Play this WAV or MP3 file when a phase finishes instead of --sound")
+            .takes_value(true)
+            .value_name("FILE"),
+        Arg::new("tick-sound")
+            .long("tick-sound")
+            .about("Tick during pomodoros, with the built-in click or a sound file")
+            .takes_value(true)
+            .min_values(0)
+            .value_name("FILE"),
+        Arg::new("tick-every")
+            .long("tick-every")
+            .about("How often to tick")
+            .takes_value(true)
+            .possible_values(&["second", "minute"])
+            .requires("tick-sound"),
+        Arg::new("block")
+            .long("block")
+            .about("Hosts to block in the hosts file during pomodoros, needs elevated permissions")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .validator(validate_host),
+        Arg::new("osc-progress")
+            .long("osc-progress")
+            .about("Show the progress in the terminal tab with OSC 9;4 escape codes"),
+        Arg::new("status-file")
+            .long("status-file")
+            .about("File to keep the symbol and remaining time in, like `🍅 12:34`")
+            .takes_value(true),
+        Arg::new("pause-on-idle")
+            .long("pause-on-idle")
+            .about("Pause while there was no keyboard or mouse input for this many seconds")
+            .takes_value(true),
+        Arg::new("pause-command")
+            .long("pause-command")
+            .about("Shell command run every 5 seconds, the timer pauses while it exits with 0")
+            .takes_value(true),
+        Arg::new("warn-at")
+            .long("warn-at")
+            .about("Seconds before the end of a pomodoro to give a heads-up")
+            .takes_value(true),
+        Arg::new("daily-goal")
+            .long("daily-goal")
+            .about("Pomodoros to finish each day, shows progress like 5/8 today")
+            .takes_value(true),
+        Arg::new("micro-reminder")
+            .long("micro-reminder")
+            .about("Notify every INTERVAL of a pomodoro with MESSAGE, like 20m:Look away, repeatable")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|value| parse_micro_reminder(value).map(|_| ())),
+        Arg::new("warn-on-resume")
+            .long("warn-on-resume")
+            .about("Also warn when a pomodoro continues with less than --warn-at left")
+            .requires("warn-at"),
+        Arg::new("snooze")
+            .long("snooze")
+            .about("Seconds to settle in before an interrupted pomodoro continues")
+            .takes_value(true),
+        Arg::new("stale-after")
+            .long("stale-after")
+            .about("Ask before continuing a pomodoro interrupted longer ago than this, like 1h")
+            .takes_value(true)
+            .validator(|value| parse_duration(value).map(|_| ())),
+        Arg::new("ask-reason")
+            .long("ask-reason")
+            .about("Ask why when a pomodoro is interrupted, the answer goes into log and history"),
+        Arg::new("note-prompt")
+            .long("note-prompt")
+            .about("Ask what you got done when a pomodoro finishes, for the history"),
+        Arg::new("reason")
+            .long("reason")
+            .about("Reason to record if this pomodoro gets interrupted, instead of asking")
+            .takes_value(true),
+        Arg::new("delay")
+            .long("delay")
+            .about("Wait this long, like 5m, before the session starts")
+            .takes_value(true)
+            .validator(|value| parse_duration(value).map(|_| ()))
+            .conflicts_with("until"),
+        Arg::new("interrupt-confirm")
+            .long("interrupt-confirm")
+            .about("Ask before Ctrl-C stops the timer, a second Ctrl-C or 10 seconds confirm"),
+        Arg::new("warmup")
+            .long("warmup")
+            .about("Seconds of countdown before each new pomodoro starts")
+            .takes_value(true),
+        Arg::new("confirm")
+            .long("confirm")
+            .about("Wait for Enter before starting the next pomodoro after a break"),
+        Arg::new("confirm-timeout")
+            .long("confirm-timeout")
+            .about("Start the next pomodoro anyway after this long, like 2m")
+            .takes_value(true)
+            .validator(|value| parse_duration(value).map(|_| ()))
+            .requires("confirm"),
+        Arg::new("label")
+            .short('l')
+            .long("label")
+            .about("Name of the task the pomodoro is for")
+            .takes_value(true),
+        Arg::new("test-scale")
+            .long("test-scale")
+            .about("Run time faster by this factor, for testing")
+            .takes_value(true)
+            .hidden(true),
+        Arg::new("no-progress")
+            .long("no-progress")
+            .about("Don't show the progress bar"),
+        Arg::new("keep-bar")
+            .long("keep-bar")
+            .about("Leave the progress bar on screen once a phase ends"),
+        Arg::new("tui")
+            .long("tui")
+            .about("Show a full-screen countdown instead of the progress bar"),
+        Arg::new("serve")
+            .long("serve")
+            .about("Answer GET /status on this port (or address like 0.0.0.0:8080) while running")
+            .takes_value(true),
+        Arg::new("json-events")
+            .long("json-events")
+            .about("Write a JSON object per second to stderr instead of the progress bar"),
+        Arg::new("ipc")
+            .long("ipc")
+            .about("Answer `pom status` through a socket next to the state file"),
+        Arg::new("silent")
+            .short('s')
+            .long("silent")
+            .about("Show notifications without playing a sound"),
+        Arg::new("lang")
+            .long("lang")
+            .about("Language of messages, prompts and notifications, warnings and errors stay English [default: en]")
+            .takes_value(true)
+            .possible_values(&["en", "de"]),
+        Arg::new("quiet-hours")
+            .long("quiet-hours")
+            .about("Keep notifications silent between these times, like 22:00-07:00")
+            .takes_value(true)
+            .validator(|value| parse_quiet_hours(value).map(|_| ())),
+        Arg::new("format")
+            .long("format")
+            .about("Format of the summary printed after every pomodoro")
+            .takes_value(true)
+            .possible_values(SummaryFormat::NAMES),
+        Arg::new("no-notify")
+            .long("no-notify")
+            .about("Don't show any desktop notifications"),
+        Arg::new("sound")
+            .long("sound")
+            .about("Notification sound, defaults to reminder")
+            .takes_value(true)
+            .possible_values(Sound::NAMES),
+        Arg::new("symbol")
+            .long("symbol")
+            .about("Symbol shown in front of a new pomodoro")
+            .takes_value(true),
+        Arg::new("progress-chars")
+            .long("progress-chars")
+            .about("Characters for the filled, current and empty part of the bar")
+            .takes_value(true)
+            .validator(validate_chars),
+        Arg::new("tick-chars")
+            .long("tick-chars")
+            .about("Characters for the spinner, the last one is shown when done")
+            .takes_value(true)
+            .validator(validate_chars),
+        Arg::new("color")
+            .long("color")
+            .about("Color of the bar, auto uses red for pomodoros and green for breaks")
+            .takes_value(true)
+            .possible_values(&["auto", "red", "green", "blue", "cyan", "magenta"]),
+        Arg::new("show-elapsed")
+            .long("show-elapsed")
+            .about("Show elapsed and total time next to the ETA"),
+        Arg::new("hide-eta")
+            .long("hide-eta")
+            .about("Focus mode: no times next to the bar, just how far along the phase is")
+            .conflicts_with_all(&["show-elapsed", "bar-template"]),
+        Arg::new("bar-template")
+            .long("bar-template")
+            .about("indicatif template for the progress bar, e.g. \"{msg} {wide_bar} {eta}\"")
+            .takes_value(true)
+            .validator(validate_template),
+        Arg::new("ascii")
+            .long("ascii")
+            .about("Draw with ASCII characters only"),
+        Arg::new("notify-start")
+            .long("notify-start")
+            .about("Show a notification when a new pomodoro starts"),
+        Arg::new("notify-resume")
+            .long("notify-resume")
+            .about("Show a notification when an interrupted pomodoro continues"),
+        Arg::new("bell")
+            .long("bell")
+            .about("Ring the terminal bell when a phase finishes, the default without a desktop"),
+        Arg::new("no-bell")
+            .long("no-bell")
+            .about("Never ring the terminal bell")
+            .conflicts_with("bell"),
+        Arg::new("bell-count")
+            .long("bell-count")
+            .about("How often to ring the terminal bell")
+            .takes_value(true),
+        Arg::new("notify-duration")
+            .long("notify-duration")
+            .about("How long notifications at the end of a phase stay, defaults to long after pomodoros and short after breaks")
+            .takes_value(true)
+            .possible_values(NotificationDuration::NAMES),
+        Arg::new("notify-title")
+            .long("notify-title")
+            .about("Notification title when a pomodoro or break finishes, may use {phase}, {label}, {duration} and {count}")
+            .takes_value(true),
+        Arg::new("notify-body")
+            .long("notify-body")
+            .about("Notification text when a pomodoro or break finishes, with the same placeholders")
+            .takes_value(true),
+        Arg::new("notify-command")
+            .long("notify-command")
+            .about("Shell command to run when a pomodoro or break finishes")
+            .takes_value(true),
+        Arg::new("notify-command-on-interrupt")
+            .long("notify-command-on-interrupt")
+            .about("Also run the notify command when a pomodoro or break is interrupted")
+            .requires("notify-command"),
+        Arg::new("metrics-file")
+            .long("metrics-file")
+            .about("Prometheus textfile to keep counters of finished and interrupted pomodoros in")
+            .takes_value(true),
+        Arg::new("webhook-url")
+            .long("webhook-url")
+            .about("URL to POST a JSON summary of every finished or interrupted pomodoro to")
+            .takes_value(true),
+        Arg::new("ntfy-topic")
+            .long("ntfy-topic")
+            .about("ntfy topic to publish a message to when a pomodoro or break finishes")
+            .takes_value(true),
+        Arg::new("ntfy-server")
+            .long("ntfy-server")
+            .about("ntfy server to publish to [default: https://ntfy.sh]")
+            .takes_value(true)
+            .requires("ntfy-topic"),
+        Arg::new("ntfy-on-interrupt")
+            .long("ntfy-on-interrupt")
+            .about("Also publish to the ntfy topic when a pomodoro or break is interrupted")
+            .requires("ntfy-topic"),
+    ]
+}
+
+/// Print every timer with an interrupted pomodoro, looking for the state files of all names
+/// next to `state_path`.
+fn list_resumable(state_path: &Path) -> Result<(), PomError> {
+    let dir = match state_path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+    let named_prefix = format!("{}-", paths::STATEFILE_NAME);
+    let mut sessions = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let name = if file_name == paths::STATEFILE_NAME {
+            None
+        } else if let Some(name) = file_name.strip_prefix(&named_prefix) {
+            Some(name.to_string())
         } else {
-            timer_duration = Duration::from_secs(u64::try_from(-self.arg_duration).unwrap())
+            continue;
+        };
+        match state::get_saved_state(&entry.path()) {
+            Ok(state) if state.seconds_remaining > 0 => sessions.push((name, state)),
+            Ok(_) => {}
+            Err(e) => warn!("Skipping state file {}: {}", entry.path().display(), e),
         }
+    }
+    if sessions.is_empty() {
+        println!("No interrupted pomodoros");
+        return Ok(());
+    }
 
-        let bar = ProgressBar::new(timer_duration.as_secs());
-        bar.set_style(
-            ProgressStyle::default_bar()
-                .template("{msg} {spinner} [{eta_precise}] [{wide_bar:.red/red}]")
-                .progress_chars("██ ")
-                .tick_chars("🔴⚪ "),
-        );
-        bar.set_message(symbol);
-
-        let one_second = Duration::from_secs(1);
-        let start = Instant::now();
-
-        info!(
-            "{} {} {} Pomodoro {}{}",
-            symbol,
-            if was_continued {
-                "Continuing"
-            } else {
-                "Starting new"
-            },
-            format_duration(timer_duration),
-            if was_continued { "" } else { "on " },
-            Local::now().format("%A, %v at %H:%M:%S")
+    sessions.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, state) in sessions {
+        print!(
+            "{:<16} {} {:02}:{:02} remaining",
+            name.as_deref().unwrap_or("(default)"),
+            state.phase.name(),
+            state.seconds_remaining / 60,
+            state.seconds_remaining % 60
         );
+        match state.label {
+            Some(label) => println!(" for '{}'", label),
+            None => println!(),
+        }
+    }
+    Ok(())
+}
 
-        while (start.elapsed() < timer_duration) && !was_interrupted {
-            std::thread::sleep(one_second);
-            bar.inc(1);
-            if self.ctrl_pressed.load(Ordering::SeqCst) {
-                was_interrupted = true;
+/// Print what the pom listening on `socket_path` is doing, or `idle`.
+///
+/// `oneline` prints just the symbol and the remaining time, `🍅 12:34`, for shell prompts.
+fn print_status(socket_path: &Path, oneline: bool) -> Result<(), PomError> {
+    match ipc::query(socket_path) {
+        Ok(Some(status)) if oneline => {
+            let style = BarStyle::unicode();
+            println!(
+                "{} {:02}:{:02}",
+                if status.paused {
+                    &style.symbol_paused
+                } else if status.phase.is_break() {
+                    &style.symbol_break
+                } else {
+                    &style.symbol
+                },
+                status.remaining / 60,
+                status.remaining % 60
+            );
+        }
+        Ok(Some(status)) => {
+            print!(
+                "{} {:02}:{:02} remaining",
+                status.phase.name(),
+                status.remaining / 60,
+                status.remaining % 60
+            );
+            if status.paused {
+                print!(" (paused)");
+            }
+            match status.label {
+                Some(label) => println!(" for '{}'", label),
+                None => println!(),
             }
         }
+        Ok(None) | Err(_) => println!("idle"),
+    }
+    Ok(())
+}
 
-        bar.finish_and_clear();
+/// The command line interface, for parsing and for shell completions.
+fn app() -> App<'static> {
+    App::new(crate_name!())
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about(crate_description!())
+        .args(timer_args())
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .about("Name of the timer, timers with different names run independently")
+                .takes_value(true)
+                .validator(validate_name)
+                .global(true),
+        )
+        .arg(
+            Arg::new("data-dir")
+                .long("data-dir")
+                .about("Directory for the state, log, history and config files")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("state-file")
+                .long("state-file")
+                .about("Path of the state file used to continue interrupted pomodoros")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .about("Path of the pomodoro log file")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .about("What goes into the log file, off disables it [default: $RUST_LOG or info]")
+                .takes_value(true)
+                .possible_values(&["off", "error", "warn", "info", "debug", "trace"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-target")
+                .long("log-target")
+                .about("Where the log goes, a stream leaves the log file alone [default: file]")
+                .takes_value(true)
+                .possible_values(&["file", "stdout", "stderr"])
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .about("Also print the log on stderr, -vv and -vvv for debug and trace messages")
+                .multiple(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-max-size")
+                .long("log-max-size")
+                .about("Rotate the log file once it grows beyond this size [default: 1M]")
+                .takes_value(true)
+                .validator(|value| parse_size(value).map(|_| ()))
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-keep")
+                .long("log-keep")
+                .about("Number of rotated log files to keep [default: 3]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("history-file")
+                .long("history-file")
+                .about("Path of the JSON lines file with one record per pomodoro")
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            App::new("start")
+                .about("Start a new pomodoro, ignoring an interrupted one")
+                .args(timer_args()),
+        )
+        .subcommand(
+            App::new("resume")
+                .about("Continue the interrupted pomodoro")
+                .arg(
+                    Arg::new("session")
+                        .about("Name of the timer to continue, like --name")
+                        .index(1)
+                        .validator(validate_name),
+                )
+                .arg(
+                    Arg::new("list")
+                        .long("list")
+                        .about("List the timers with an interrupted pomodoro")
+                        .conflicts_with("session"),
+                )
+                .args(timer_args()),
+        )
+        .subcommand(App::new("reset").about("Clear the saved state"))
+        .subcommand(
+            App::new("test-notify")
+                .about("Show the notification of a finished pomodoro now, to check it works")
+                .args(timer_args()),
+        )
+        .subcommand(
+            App::new("status")
+                .about("Show the status of a pom running with --ipc")
+                .arg(
+                    Arg::new("oneline")
+                        .long("oneline")
+                        .about("Print just `🍅 12:34` or `idle`, for shell prompts"),
+                )
+                .arg(
+                    Arg::new("status-file")
+                        .long("status-file")
+                        .about("Read the status from the --status-file of a running pom instead")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("doctor")
+                .about("Check paths, terminal and notifications, and show a test notification"),
+        )
+        .subcommand(
+            App::new("stats")
+                .about("Summarize the pomodoro log")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .about("Print the statistics as JSON"),
+                )
+                .arg(
+                    Arg::new("by-label")
+                        .long("by-label")
+                        .about("Total pomodoros and focused time per label from the history"),
+                ),
+        )
+        .subcommand(
+            App::new("history")
+                .about("List the most recent pomodoros")
+                .arg(
+                    Arg::new("last")
+                        .long("last")
+                        .about("Number of pomodoros to list [default: 10]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .about("Only list pomodoros started on or after this date, like 2020-10-05")
+                        .takes_value(true)
+                        .validator(|value| {
+                            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|_| ())
+                        }),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .about("Print the pomodoros as JSON"),
+                ),
+        )
+        .subcommand(
+            App::new("export")
+                .about("Write the whole history as CSV or JSON")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .about("Format of the export [default: csv]")
+                        .takes_value(true)
+                        .possible_values(&["csv", "json"]),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .about("File to write, stdout without one")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            App::new("completions")
+                .about("Print a shell completion script")
+                .arg(
+                    Arg::new("shell")
+                        .about("Shell to complete for")
+                        .required(true)
+                        .possible_values(&["bash", "zsh", "fish", "powershell", "elvish"]),
+                ),
+        )
+}
 
-        if was_interrupted {
-            let time_remaining = timer_duration - start.elapsed();
+/// The config file with the `--profile` applied.
+fn load_config(matches: &clap::ArgMatches) -> Result<Config, PomError> {
+    let config = match paths::config_file(matches.value_of("data-dir")) {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+    match matches.value_of("profile") {
+        Some(name) => config.with_profile(name),
+        None => Ok(config),
+    }
+}
 
-            _info_and_print(&format!(
-                "Interrupted at {} with {} remaining.",
-                Local::now().format("%H:%M:%S"),
-                HumanDuration(time_remaining)
-            ));
-            PomApp::save_state(time_remaining.as_secs());
-        } else {
-            _info_and_print(&format!("Finished at {}", Local::now().format("%H:%M:%S")));
-            PomApp::save_state(0);
-        }
+/// The length of a pomodoro.
+///
+/// Command line arguments win over POM_* environment variables, which win over the
+/// config file.
+fn pomodoro_duration(
+    matches: &clap::ArgMatches,
+    config: &Config,
+    last_state: &SavedState,
+) -> Result<Duration, PomError> {
+    match (matches.value_of("until"), matches.value_of("duration")) {
+        (Some(until), _) => parse_until(until, Local::now()),
+        (None, Some(duration)) => parse_pomodoro_duration(duration),
+        (None, None) => match env_var("POM_DURATION")? {
+            Some(duration) => {
+                parse_pomodoro_duration(&duration).map_err(|e| format!("POM_DURATION: {}", e))
+            }
+            // --restart without a duration starts over with the length of the interrupted
+            // pomodoro.
+            None if matches.is_present("restart")
+                && last_state.seconds_remaining > 0
+                && last_state.planned_seconds > 0 =>
+            {
+                Ok(Duration::from_secs(last_state.planned_seconds))
+            }
+            None => parse_pomodoro_duration(&config.work_minutes.to_string()),
+        },
+    }
+    .map_err(PomError::InvalidArgument)
+}
 
-        io::stdout().flush().unwrap();
+fn label_arg(matches: &clap::ArgMatches) -> Result<Option<String>, PomError> {
+    match matches.value_of("label") {
+        Some(label) => Ok(Some(label.to_string())),
+        None => env_var("POM_LABEL"),
+    }
+}
 
-        if !was_interrupted {
-            Toast::new(Toast::POWERSHELL_APP_ID)
-                .title("Pomodoro finished!")
-                .text1("Your pomodoro has finished.")
-                .sound(Some(Sound::Reminder))
-                .duration(WinRtDuration::Short)
-                .show()
-                .expect("unable to toast");
+/// Show the notification of a finished pomodoro right away, to see if notifications work.
+fn test_notify(
+    alerts: &Alerts,
+    lang: Lang,
+    label: Option<&str>,
+    duration: Duration,
+    completed_today: u32,
+) -> Result<(), PomError> {
+    let (title, text) = alerts.finished_text(lang, Phase::Work, label, duration, completed_today);
+    println!(
+        "Showing '{}' with sound {:?}",
+        title,
+        alerts.notification_sound()
+    );
+    let duration = alerts.finished_duration(Phase::Work);
+    match notify::default_notifier(alerts.notification_sound(), duration).show(&title, &text) {
+        Ok(()) => {
+            println!("Notification shown");
+            alerts.play_chime();
+            Ok(())
         }
+        Err(e) => Err(PomError::Notification(e.to_string())),
+    }
+}
+
+/// Write the completion script for `shell` to stdout.
+fn print_completions(shell: &str) {
+    let mut app = app();
+    let mut stdout = io::stdout();
+    match shell {
+        "bash" => generate::<Bash, _>(&mut app, crate_name!(), &mut stdout),
+        "zsh" => generate::<Zsh, _>(&mut app, crate_name!(), &mut stdout),
+        "fish" => generate::<Fish, _>(&mut app, crate_name!(), &mut stdout),
+        "powershell" => generate::<PowerShell, _>(&mut app, crate_name!(), &mut stdout),
+        "elvish" => generate::<Elvish, _>(&mut app, crate_name!(), &mut stdout),
+        _ => unreachable!("clap checked the shell"),
     }
 }
 
 /// Configure logging, initialize the app, and run it.
-fn main() {
-    fn get_saved_state(state: &mut SavedState) {
-        let input = File::open(STATEFILE_NAME);
+fn try_main() -> Result<(), PomError> {
+    let matches = app().get_matches();
 
-        let temp_state: SavedState = match input {
-            Ok(input) => serde_json::from_reader(input).expect("error while reading json"),
-            Err(_e) => SavedState {
-                seconds_remaining: 0,
-            },
+    // Without a subcommand pom starts a new pomodoro, like `pom start`.
+    let (command, matches) = match matches.subcommand() {
+        Some(("completions", completions_matches)) => {
+            print_completions(completions_matches.value_of("shell").unwrap_or_default());
+            return Ok(());
+        }
+        Some(("stats", stats_matches)) => {
+            let history_path = paths::history_path(stats_matches)?;
+            if stats_matches.is_present("by-label") {
+                return print_label_totals(&history_path, stats_matches.is_present("json"));
+            }
+            // A broken state file only costs the streak.
+            let state =
+                state::get_saved_state(&paths::state_path(stats_matches)?).unwrap_or_default();
+            return print_stats(
+                &paths::log_path(stats_matches)?,
+                &history_path,
+                &state,
+                stats_matches.is_present("json"),
+            );
+        }
+        Some(("doctor", doctor_matches)) => {
+            return doctor(
+                &paths::state_path(doctor_matches)?,
+                &paths::log_path(doctor_matches)?,
+                &paths::history_path(doctor_matches)?,
+                paths::config_file(doctor_matches.value_of("data-dir")).as_deref(),
+            );
+        }
+        Some(("export", export_matches)) => {
+            return export_history(
+                &paths::history_path(export_matches)?,
+                export_matches.value_of("format") == Some("json"),
+                export_matches.value_of("output").map(Path::new),
+            );
+        }
+        Some(("history", history_matches)) => {
+            let since = history_matches
+                .value_of("since")
+                .map(|since| NaiveDate::parse_from_str(since, "%Y-%m-%d"))
+                .transpose()
+                .expect("clap checked the date");
+            return print_history(
+                &paths::history_path(history_matches)?,
+                parse_arg(history_matches, "last", "10")?,
+                since,
+                history_matches.is_present("json"),
+            );
+        }
+        Some((command, sub_matches)) => (command, sub_matches),
+        None => ("start", &matches),
+    };
+
+    let name = paths::timer_name(matches);
+    let state_path = paths::state_path(matches)?;
+    let socket_path = paths::socket_path(&state_path, matches);
+    if command == "status" {
+        if let Some(path) = matches.value_of("status-file") {
+            // Written by `--status-file` in the one line format already.
+            let text = fs::read_to_string(path).unwrap_or_else(|_| "idle\n".to_string());
+            print!("{}", text);
+            return Ok(());
+        }
+        return print_status(&socket_path, matches.is_present("oneline"));
+    }
+    if command == "resume" && matches.is_present("list") {
+        return list_resumable(&state_path);
+    }
+    if command == "reset" {
+        // Today's count is history, not state of an interrupted pomodoro.
+        let last_state = state::get_saved_state(&state_path).unwrap_or_default();
+        let state = SavedState {
+            completed_today: last_state.completed_today,
+            completed_on: last_state.completed_on,
+            streak: last_state.streak,
+            best_streak: last_state.best_streak,
+            ..SavedState::default()
         };
-        state.seconds_remaining = temp_state.seconds_remaining;
+        state::save_state(&state_path, &state)?;
+        println!("State cleared");
+        return Ok(());
+    }
+    if command == "test-notify" {
+        // Only what the notification needs, without blocking hosts, servers or the log.
+        let last_state = state::get_saved_state(&state_path).unwrap_or_default();
+        let config = load_config(matches)?;
+        return test_notify(
+            &Alerts::new(matches, &config)?,
+            parse_arg(&matches, "lang", "en")?,
+            label_arg(matches)?.as_deref(),
+            pomodoro_duration(matches, &config, &last_state)?,
+            last_state.completed_today,
+        );
     }
 
-    simple_logging::log_to(
-        OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(LOGFILE_NAME)
-            .unwrap(),
-        LevelFilter::Info,
-    );
+    let log_path = paths::log_path(matches)?;
+
+    let log_level = match matches.value_of("log-level") {
+        Some(level) => level.parse().expect("clap checked the level"),
+        None => match env::var("RUST_LOG") {
+            Ok(level) => level.parse().unwrap_or_else(|_| {
+                eprintln!("Warning: ignoring unknown RUST_LOG level '{}'", level);
+                LevelFilter::Info
+            }),
+            Err(_) => LevelFilter::Info,
+        },
+    };
+    // -v mirrors the log to stderr, -vv and -vvv raise the level to debug and trace.
+    let verbose = matches.occurrences_of("verbose");
+    let log_level = match verbose {
+        0 => log_level,
+        1 => log_level.max(LevelFilter::Info),
+        2 => log_level.max(LevelFilter::Debug),
+        _ => LevelFilter::Trace,
+    };
+    match matches.value_of("log-target") {
+        _ if log_level == LevelFilter::Off => {}
+        Some("stdout") if verbose > 0 => {
+            simple_logging::log_to(Tee(io::stdout(), io::stderr()), log_level)
+        }
+        Some("stdout") => simple_logging::log_to(io::stdout(), log_level),
+        Some("stderr") => simple_logging::log_to_stderr(log_level),
+        _ => {
+            let max_size = parse_size(matches.value_of("log-max-size").unwrap_or("1M"))
+                .map_err(PomError::InvalidArgument)?;
+            logfile::rotate(&log_path, max_size, parse_arg(&matches, "log-keep", "3")?)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&log_path)?;
+            if verbose > 0 {
+                simple_logging::log_to(Tee(file, io::stderr()), log_level);
+            } else {
+                simple_logging::log_to(file, log_level);
+            }
+        }
+    }
 
     let irq = Arc::new(AtomicBool::new(false));
 
+    // With the `termination` feature this also covers SIGTERM and SIGHUP, and closing
+    // the console window on Windows, so the remaining time is saved in all these cases.
     let irq_c = irq.clone();
     ctrlc::set_handler(move || {
         irq_c.store(true, Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
+    })?;
+    // signal-hook calls the handler installed before its own, so these still set `irq` too.
+    let terminated = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    for &signal in &[signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP] {
+        signal_hook::flag::register(signal, terminated.clone())?;
+    }
 
-    let mut last_state = SavedState {
-        seconds_remaining: 0,
+    let last_state = match state::get_saved_state(&state_path) {
+        Err(PomError::State(e)) => {
+            warn!("Ignoring corrupt state file: {}", e);
+            eprintln!(
+                "Warning: ignoring corrupt state file {} ({})",
+                state_path.display(),
+                e
+            );
+            SavedState::default()
+        }
+        state => state?,
     };
-    get_saved_state(&mut last_state);
+    if command == "resume" && last_state.seconds_remaining == 0 {
+        return Err(PomError::NothingToResume(name.map(String::from)));
+    }
 
-    let matches = App::new(crate_name!())
-        .version(crate_version!())
-        .author(crate_authors!())
-        .about(crate_description!())
-        .arg(
-            Arg::new("duration")
-                .short('d')
-                .long("duration")
-                .about("Duration in minutes, defaults to 25")
-                .takes_value(true)
-                .allow_hyphen_values(true),
-        )
-        .arg(
-            Arg::new("restart")
-                .short('r')
-                .long("restart")
-                .about("Restart a new pomodoro"),
-        )
-        .get_matches();
+    let once = matches.is_present("once");
+    if once && command == "resume" {
+        return Err(PomError::InvalidArgument(
+            "--once never continues a pomodoro, it can't be resumed".to_string(),
+        ));
+    }
+    // Only `pom resume` continues the interrupted pomodoro, everything else starts over.
+    let mut arg_restart = command != "resume";
+    let lang: Lang = parse_arg(&matches, "lang", "en")?;
+    // A stale one is continued only after asking, if `--stale-after` says so.
+    let may_continue = !arg_restart;
+    if let (Some(stale_after), Some(saved_at), true) = (
+        matches.value_of("stale-after"),
+        last_state.saved_at,
+        may_continue,
+    ) {
+        let stale_after = parse_duration(stale_after).map_err(PomError::InvalidArgument)?;
+        let ago = (Local::now() - saved_at).to_std().unwrap_or_default();
+        if ago > stale_after {
+            match ask_stale_resume(lang, ago)? {
+                StaleChoice::Resume => {}
+                StaleChoice::Restart => arg_restart = true,
+                StaleChoice::Quit => process::exit(EXIT_INTERRUPTED),
+            }
+        }
+    }
+    let task_file = match matches.value_of("from-file") {
+        Some(path) => {
+            let path = fs::canonicalize(path)?;
+            arg_restart |= last_state.task_file.as_ref() != Some(&path);
+            Some(path)
+        }
+        None if !arg_restart && last_state.seconds_remaining > 0 => last_state.task_file.clone(),
+        None => None,
+    };
+    let tasks = match &task_file {
+        Some(path) => tasks::load(path)?,
+        None => Vec::new(),
+    };
 
-    let duration: i32 = matches
-        .value_of("duration")
-        .unwrap_or("25")
-        .parse()
-        .unwrap();
+    let config = load_config(matches)?;
+    let duration = pomodoro_duration(matches, &config, &last_state)?;
+    let label = label_arg(matches)?;
+    let break_short = parse_break_minutes(
+        matches
+            .value_of("break-short")
+            .unwrap_or(&config.short_break_minutes.to_string()),
+    )
+    .map_err(PomError::InvalidArgument)?;
+    let break_long = parse_break_minutes(
+        matches
+            .value_of("break-long")
+            .unwrap_or(&config.long_break_minutes.to_string()),
+    )
+    .map_err(PomError::InvalidArgument)?;
+    let long_break_interval: u32 = parse_arg(&matches, "long-break-interval", "4")?;
+    let count: u32 = parse_arg(&matches, "count", "1")?;
+
+    let mut style = if matches.is_present("ascii") {
+        BarStyle::ascii()
+    } else {
+        BarStyle::unicode()
+    };
+    if let Some(symbol) = matches
+        .value_of("symbol")
+        .map(String::from)
+        .or_else(|| config.symbol.clone())
+    {
+        style.symbol = symbol;
+    }
+    if let Some(chars) = matches.value_of("progress-chars") {
+        style.progress_chars = chars.to_string();
+    }
+    if let Some(chars) = matches.value_of("tick-chars") {
+        style.tick_chars = chars.to_string();
+        style.tick_chars_break = chars.to_string();
+    }
+    style.color = matches
+        .value_of("color")
+        .filter(|&color| color != "auto")
+        .map(String::from);
+    style.show_elapsed = matches.is_present("show-elapsed");
+    style.hide_eta = matches.is_present("hide-eta");
+    style.template = matches.value_of("bar-template").map(String::from);
+    style.no_color = env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+
+    let alerts = Alerts::new(matches, &config)?;
+    let block: Vec<String> = matches
+        .values_of("block")
+        .map_or_else(Vec::new, |hosts| hosts.map(String::from).collect());
+    if !block.is_empty() {
+        HostBlock::prepare()?;
+    }
+
+    let pause_on_idle = match matches.value_of("pause-on-idle") {
+        Some(_) if idle::idle_time().is_none() => {
+            warn!("Idle detection is not available, --pause-on-idle has no effect");
+            eprintln!("Warning: idle detection is not available, --pause-on-idle has no effect");
+            None
+        }
+        Some(_) => Some(Duration::from_secs(parse_arg(
+            &matches,
+            "pause-on-idle",
+            "",
+        )?)),
+        None => None,
+    };
+
+    let status_server = if matches.is_present("ipc") {
+        match StatusServer::start(&socket_path) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                warn!("Unable to open status socket: {}", e);
+                eprintln!("Warning: unable to open status socket ({})", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let http_status_server = match matches.value_of("serve") {
+        Some(address) => {
+            // A plain port is only reachable from this machine.
+            let address = match address.parse::<u16>() {
+                Ok(port) => format!("127.0.0.1:{}", port),
+                Err(_) => address.to_string(),
+            };
+            match HttpStatusServer::start(&address) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    warn!("Unable to start status server: {}", e);
+                    eprintln!("Warning: unable to start status server ({})", e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let ticker = if matches.is_present("tick-sound") && !alerts.silent {
+        Some(Ticker::new(matches.value_of("tick-sound").map(Path::new))?)
+    } else {
+        None
+    };
 
     let mut app: PomApp = PomApp {
-        arg_restart: matches.is_present("restart"),
+        arg_restart,
         arg_duration: duration,
+        break_short,
+        break_long,
+        cycles_before_long_break: long_break_interval,
         ctrl_pressed: &irq,
+        terminated: &terminated,
         saved_state: &last_state,
+        state_path: if once { None } else { Some(state_path) },
+        history_path: paths::history_path(matches)?,
+        metrics_path: matches.value_of("metrics-file").map(PathBuf::from),
+        label,
+        time_scale: parse_arg::<u32>(&matches, "test-scale", "1")?.max(1),
+        status_server,
+        http_status_server,
+        // indicatif already hides the bar if stderr is no terminal, but a redirected
+        // stdout means pom runs from a script as well.
+        show_progress: !matches.is_present("no-progress") && atty::is(atty::Stream::Stdout),
+        keep_bar: matches.is_present("keep-bar"),
+        tui: matches.is_present("tui") && atty::is(atty::Stream::Stdout),
+        json_events: matches.is_present("json-events"),
+        summary_format: parse_arg(&matches, "format", "plain")?,
+        alerts,
+        lang,
+        style,
+        notify_start: matches.is_present("notify-start"),
+        notify_resume: matches.is_present("notify-resume"),
+        notify_command: matches.value_of("notify-command").map(String::from),
+        notify_command_on_interrupt: matches.is_present("notify-command-on-interrupt"),
+        webhook_url: matches.value_of("webhook-url").map(String::from),
+        ntfy_topic: matches.value_of("ntfy-topic").map(String::from),
+        ntfy_server: matches
+            .value_of("ntfy-server")
+            .unwrap_or("https://ntfy.sh")
+            .to_string(),
+        ntfy_on_interrupt: matches.is_present("ntfy-on-interrupt"),
+        pomodoros_completed: last_state.pomodoros_completed,
+        completed_today: last_state.completed_today,
+        completed_on: last_state.completed_on,
+        daily_goal: match matches.value_of("daily-goal") {
+            Some(_) => Some(parse_arg(&matches, "daily-goal", "")?),
+            None => config.daily_goal,
+        }
+        .filter(|&goal| goal > 0),
+        streak: last_state.streak,
+        best_streak: last_state.best_streak,
+        count: count.max(1),
+        set_index: 0,
+        set_focus_seconds: 0,
+        set_interruptions: 0,
+        repeat: matches.is_present("repeat") || matches.is_present("max-cycles"),
+        max_cycles: match matches.value_of("max-cycles") {
+            Some(_) => Some(parse_arg(&matches, "max-cycles", "")?),
+            None => None,
+        },
+        task_file,
+        tasks,
+        ticker,
+        tick_every: match matches.value_of("tick-every") {
+            Some("minute") => 60,
+            _ => 1,
+        },
+        block,
+        osc_progress: matches.is_present("osc-progress") && atty::is(atty::Stream::Stdout),
+        status_file: matches.value_of("status-file").map(PathBuf::from),
+        pause_on_idle,
+        pause_command: matches.value_of("pause-command").map(String::from),
+        warn_at: match matches.value_of("warn-at") {
+            Some(_) => Some(Duration::from_secs(parse_arg(&matches, "warn-at", "")?)),
+            None => None,
+        },
+        warn_on_resume: matches.is_present("warn-on-resume"),
+        micro_reminders: matches
+            .values_of("micro-reminder")
+            .into_iter()
+            .flatten()
+            .map(parse_micro_reminder)
+            .collect::<Result<_, _>>()
+            .map_err(PomError::InvalidArgument)?,
+        snooze: Duration::from_secs(parse_arg(&matches, "snooze", "0")?),
+        warmup: Duration::from_secs(parse_arg(&matches, "warmup", "0")?),
+        delay: match matches.value_of("delay") {
+            Some(delay) => parse_duration(delay).map_err(PomError::InvalidArgument)?,
+            None => Duration::from_secs(0),
+        },
+        interrupt_confirm: matches.is_present("interrupt-confirm"),
+        reason: matches.value_of("reason").map(String::from),
+        ask_reason: matches.is_present("ask-reason"),
+        note_prompt: matches.is_present("note-prompt"),
+        confirm: matches.is_present("confirm"),
+        confirm_timeout: match matches.value_of("confirm-timeout") {
+            Some(timeout) => Some(parse_duration(timeout).map_err(PomError::InvalidArgument)?),
+            None => None,
+        },
     };
+    if !app.run()? {
+        // Let scripts tell an interrupted session from a finished one.
+        drop(app);
+        process::exit(EXIT_INTERRUPTED);
+    }
+    Ok(())
+}
+
+/// Run pom, an error is printed and ends it with exit code 1.
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("pom: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::offset::LocalResult;
+    use chrono::{FixedOffset, NaiveDateTime};
+    use pom::testing::FakeClock;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    /// Keys typed once the fake clock reaches their time.
+    struct FakeKeyboard<'a> {
+        clock: &'a FakeClock,
+        keys: RefCell<VecDeque<(Duration, Key)>>,
+    }
+
+    impl FakeKeyboard<'_> {
+        fn new<'a>(clock: &'a FakeClock, keys: Vec<(Duration, Key)>) -> FakeKeyboard<'a> {
+            FakeKeyboard {
+                clock,
+                keys: RefCell::new(keys.into()),
+            }
+        }
+
+        /// Wait up to `timeout` for the next key, like `keys::poll_key`.
+        fn poll(&self, timeout: Duration) -> Result<Option<Key>, PomError> {
+            let now = self.clock.elapsed();
+            let mut keys = self.keys.borrow_mut();
+            match keys.front() {
+                Some(&(at, _)) if at <= now + timeout => {
+                    self.clock.sleep(at.saturating_sub(now));
+                    Ok(keys.pop_front().map(|(_, key)| key))
+                }
+                _ => {
+                    self.clock.sleep(timeout);
+                    Ok(None)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn answer_is_read_until_enter() {
+        let clock = FakeClock::new();
+        let keyboard = FakeKeyboard::new(
+            &clock,
+            vec![
+                (secs(1), Key::Char('o')),
+                (secs(2), Key::Char('x')),
+                (secs(3), Key::Backspace),
+                (secs(4), Key::Char('k')),
+                (secs(5), Key::Enter),
+                (secs(6), Key::Char(' ')),
+            ],
+        );
+        let interrupt = AtomicBool::new(false);
+        let mut echo = Vec::new();
+
+        let answer = read_answer(
+            &clock,
+            ANSWER_TIMEOUT,
+            |timeout| keyboard.poll(timeout),
+            &mut echo,
+            &interrupt,
+        )
+        .unwrap();
+
+        assert_eq!(answer.as_deref(), Some("ok"));
+        assert_eq!(echo, b"ox\x08 \x08k");
+        assert_eq!(keyboard.poll(secs(1)).unwrap(), Some(Key::Char(' ')));
+    }
+
+    #[test]
+    fn timed_out_answer_leaves_the_next_keys_to_the_timer() {
+        let clock = FakeClock::new();
+        let keyboard = FakeKeyboard::new(
+            &clock,
+            vec![(secs(31), Key::Char(' ')), (secs(32), Key::Char('s'))],
+        );
+        let interrupt = AtomicBool::new(false);
+        let mut echo = Vec::new();
+
+        let answer = read_answer(
+            &clock,
+            ANSWER_TIMEOUT,
+            |timeout| keyboard.poll(timeout),
+            &mut echo,
+            &interrupt,
+        )
+        .unwrap();
+
+        assert_eq!(answer, None);
+        assert!(echo.is_empty());
+        // The next phase polls the same keyboard and gets every key typed since.
+        assert_eq!(keyboard.poll(secs(5)).unwrap(), Some(Key::Char(' ')));
+        assert_eq!(keyboard.poll(secs(5)).unwrap(), Some(Key::Char('s')));
+    }
+
+    #[test]
+    fn ctrl_c_skips_the_answer_and_interrupts() {
+        let clock = FakeClock::new();
+        let keyboard = FakeKeyboard::new(
+            &clock,
+            vec![(secs(1), Key::Char('a')), (secs(2), Key::CtrlC)],
+        );
+        let interrupt = AtomicBool::new(false);
+
+        let answer = read_answer(
+            &clock,
+            ANSWER_TIMEOUT,
+            |timeout| keyboard.poll(timeout),
+            &mut io::sink(),
+            &interrupt,
+        )
+        .unwrap();
+
+        assert_eq!(answer, None);
+        assert!(interrupt.load(Ordering::SeqCst));
+    }
+
+    /// Berlin in spring 2021, where the clocks went from 02:00 straight to 03:00 on March 28.
+    #[derive(Clone, Copy)]
+    struct SpringForward;
+
+    impl SpringForward {
+        fn change() -> NaiveDateTime {
+            NaiveDate::from_ymd(2021, 3, 28).and_hms(1, 0, 0)
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
 
-    app.run();
+        fn from_offset(_: &FixedOffset) -> SpringForward {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let skipped = SpringForward::change() + chrono::Duration::hours(1);
+            if *local < skipped {
+                LocalResult::Single(FixedOffset::east(3600))
+            } else if *local < skipped + chrono::Duration::hours(1) {
+                LocalResult::None
+            } else {
+                LocalResult::Single(FixedOffset::east(7200))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < SpringForward::change() {
+                FixedOffset::east(3600)
+            } else {
+                FixedOffset::east(7200)
+            }
+        }
+    }
+
+    #[test]
+    fn durations_shorter_than_a_minute_are_only_refused_for_pomodoros() {
+        assert_eq!(parse_duration("30s"), Ok(secs(30)));
+        assert_eq!(parse_duration("1.5"), Ok(secs(90)));
+        assert_eq!(parse_duration("1h30m"), Ok(secs(90 * 60)));
+        assert!(parse_duration("25h").is_err());
+        assert!(parse_duration("soon").is_err());
+
+        assert_eq!(parse_pomodoro_duration("25"), Ok(secs(25 * 60)));
+        assert!(parse_pomodoro_duration("30s").is_err());
+        assert!(parse_pomodoro_duration("25h").is_err());
+    }
+
+    #[test]
+    fn until_a_time_already_past_runs_until_tomorrow() {
+        let now = FixedOffset::east(7200).ymd(2021, 6, 1).and_hms(18, 0, 0);
+
+        assert_eq!(parse_until("18:30", now), Ok(secs(30 * 60)));
+        assert_eq!(parse_until("09:00", now), Ok(secs(15 * 60 * 60)));
+        assert_eq!(parse_until("18:00", now), Ok(secs(24 * 60 * 60)));
+        assert!(parse_until("18:00", now - chrono::Duration::seconds(30)).is_err());
+        assert!(parse_until("6pm", now).is_err());
+    }
+
+    #[test]
+    fn until_counts_real_time_across_a_daylight_saving_change() {
+        let now = SpringForward.ymd(2021, 3, 28).and_hms(0, 30, 0);
+
+        assert_eq!(parse_until("03:30", now), Ok(secs(2 * 60 * 60)));
+        assert!(parse_until("02:30", now).is_err());
+    }
+
+    #[test]
+    fn quiet_hours_may_wrap_past_midnight() {
+        let time = |hour, minute| NaiveTime::from_hms(hour, minute, 0);
+        let hours = parse_quiet_hours("22:00-07:00").unwrap();
+
+        assert!(in_quiet_hours(hours, time(23, 30)));
+        assert!(in_quiet_hours(hours, time(3, 0)));
+        assert!(!in_quiet_hours(hours, time(7, 0)));
+        assert!(!in_quiet_hours(hours, time(12, 0)));
+
+        let hours = parse_quiet_hours("12:00 - 13:00").unwrap();
+        assert!(in_quiet_hours(hours, time(12, 30)));
+        assert!(!in_quiet_hours(hours, time(23, 30)));
+        assert!(parse_quiet_hours("22:00").is_err());
+    }
+
+    #[test]
+    fn the_notification_templates_apply_to_breaks_too() {
+        let alerts = Alerts {
+            notify: false,
+            silent: true,
+            quiet_hours: None,
+            sound: Sound::None,
+            chime: None,
+            bell: 0,
+            notify_duration: None,
+            notify_title: Some("{phase} done".to_string()),
+            notify_body: Some("{duration} for {label}, #{count} today".to_string()),
+        };
+
+        let (title, body) =
+            alerts.finished_text(Lang::En, Phase::ShortBreak, Some("coding"), secs(300), 2);
+
+        assert_eq!(title, format!("{} done", Phase::ShortBreak.name()));
+        assert_eq!(body, "5m for coding, #2 today");
+    }
 }
@@ -0,0 +1,134 @@
+//! Counters in the Prometheus text format, for node_exporter's textfile collector.
+//!
+//! The file itself keeps the counts, so they carry over from one run to the next.
+
+use crate::error::PomError;
+use crate::state;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub completed_total: u64,
+    pub interrupted_total: u64,
+    pub focus_seconds_total: u64,
+}
+
+impl Metrics {
+    /// Read the counters from `path`, a missing file means all are 0.
+    pub fn load(path: &Path) -> Result<Metrics, PomError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Metrics::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut metrics = Metrics::default();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let mut fields = line.split_whitespace();
+            let (name, value) = match (fields.next(), fields.next().map(str::parse)) {
+                (Some(name), Some(Ok(value))) => (name, value),
+                _ => continue,
+            };
+            match name {
+                "rusty_pom_completed_total" => metrics.completed_total = value,
+                "rusty_pom_interrupted_total" => metrics.interrupted_total = value,
+                "rusty_pom_focus_seconds_total" => metrics.focus_seconds_total = value,
+                _ => {}
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Replace `path` in one rename, so a scrape never sees a half written file.
+    pub fn save(&self, path: &Path) -> Result<(), PomError> {
+        let text = format!(
+            "# HELP rusty_pom_completed_total Pomodoros run to completion or skipped.\n\
+             # TYPE rusty_pom_completed_total counter\n\
+             rusty_pom_completed_total {}\n\
+             # HELP rusty_pom_interrupted_total Pomodoros interrupted before the end.\n\
+             # TYPE rusty_pom_interrupted_total counter\n\
+             rusty_pom_interrupted_total {}\n\
+             # HELP rusty_pom_focus_seconds_total Seconds spent in pomodoros.\n\
+             # TYPE rusty_pom_focus_seconds_total gauge\n\
+             rusty_pom_focus_seconds_total {}\n",
+            self.completed_total, self.interrupted_total, self.focus_seconds_total
+        );
+        state::write_atomically(path, &text)?;
+        Ok(())
+    }
+}
+
+/// Add a pomodoro that ran for `seconds` to the counters in `path`, a skipped one counts
+/// as completed.
+pub fn record(path: &Path, completed: bool, seconds: u64) -> Result<(), PomError> {
+    let mut metrics = Metrics::load(path)?;
+    if completed {
+        metrics.completed_total += 1;
+    } else {
+        metrics.interrupted_total += 1;
+    }
+    metrics.focus_seconds_total += seconds;
+    metrics.save(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pom.prom");
+        let metrics = Metrics {
+            completed_total: 12,
+            interrupted_total: 3,
+            focus_seconds_total: 19_800,
+        };
+
+        metrics.save(&path).unwrap();
+
+        assert_eq!(Metrics::load(&path).unwrap(), metrics);
+    }
+
+    #[test]
+    fn a_missing_file_counts_from_zero() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pom.prom");
+
+        record(&path, true, 1500).unwrap();
+        record(&path, false, 600).unwrap();
+
+        assert_eq!(
+            Metrics::load(&path).unwrap(),
+            Metrics {
+                completed_total: 1,
+                interrupted_total: 1,
+                focus_seconds_total: 2100,
+            }
+        );
+    }
+
+    #[test]
+    fn corrupt_lines_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pom.prom");
+        fs::write(
+            &path,
+            "rusty_pom_completed_total 7\n\
+             rusty_pom_interrupted_total lots\n\
+             garbage\n\
+             rusty_pom_focus_seconds_tot",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Metrics::load(&path).unwrap(),
+            Metrics {
+                completed_total: 7,
+                ..Metrics::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Desktop notifications for the platforms RustyPom runs on.
+
+use std::error::Error;
+use std::str::FromStr;
+
+/// Something that can pop up a desktop notification.
+pub trait Notification {
+    fn show(&self, title: &str, body: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// The sound played along with a notification.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sound {
+    None,
+    Default,
+    Reminder,
+    Sms,
+    Alarm,
+}
+
+impl Sound {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &["default", "reminder", "sms", "alarm", "none"];
+}
+
+impl FromStr for Sound {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(Sound::None),
+            "default" => Ok(Sound::Default),
+            "reminder" => Ok(Sound::Reminder),
+            "sms" => Ok(Sound::Sms),
+            "alarm" => Ok(Sound::Alarm),
+            _ => Err(format!(
+                "unknown sound '{}', expected one of {}",
+                name,
+                Sound::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// How long a notification stays on screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationDuration {
+    /// A few seconds.
+    Short,
+    /// Until dismissed, where the platform allows it.
+    Long,
+}
+
+impl NotificationDuration {
+    /// The names accepted by `from_str`.
+    pub const NAMES: &'static [&'static str] = &["short", "long"];
+}
+
+impl FromStr for NotificationDuration {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "short" => Ok(NotificationDuration::Short),
+            "long" => Ok(NotificationDuration::Long),
+            _ => Err(format!(
+                "unknown notification duration '{}', expected one of {}",
+                name,
+                NotificationDuration::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Whether desktop notifications can be shown at all, not on a headless box or over SSH.
+pub fn desktop_available() -> bool {
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| std::env::var_os(var).map_or(false, |value| !value.is_empty()))
+    } else {
+        std::env::var_os("SSH_CONNECTION").is_none()
+    }
+}
+
+/// Return the notification backend for the current platform.
+pub fn default_notifier(sound: Sound, duration: NotificationDuration) -> Box<dyn Notification> {
+    Box::new(PlatformNotification { sound, duration })
+}
+
+struct PlatformNotification {
+    sound: Sound,
+    duration: NotificationDuration,
+}
+
+#[cfg(windows)]
+impl Notification for PlatformNotification {
+    fn show(&self, title: &str, body: &str) -> Result<(), Box<dyn Error>> {
+        use winrt_notification::{Duration, LoopableSound, Sound as WinRtSound, Toast};
+
+        let sound = match self.sound {
+            Sound::None => None,
+            Sound::Default => Some(WinRtSound::Default),
+            Sound::Reminder => Some(WinRtSound::Reminder),
+            Sound::Sms => Some(WinRtSound::SMS),
+            Sound::Alarm => Some(WinRtSound::Single(LoopableSound::Alarm)),
+        };
+        Toast::new(Toast::POWERSHELL_APP_ID)
+            .title(title)
+            .text1(body)
+            .sound(sound)
+            .duration(match self.duration {
+                NotificationDuration::Short => Duration::Short,
+                NotificationDuration::Long => Duration::Long,
+            })
+            .show()
+            .map_err(|e| format!("{:?}", e))?;
+        Ok(())
+    }
+}
+
+/// How long a notification stays is up to its alert style in the system preferences, so
+/// `duration` has no effect.
+#[cfg(target_os = "macos")]
+impl Notification for PlatformNotification {
+    fn show(&self, title: &str, body: &str) -> Result<(), Box<dyn Error>> {
+        let sound = match self.sound {
+            Sound::None => None,
+            Sound::Default => Some("Glass"),
+            Sound::Reminder => Some("Ping"),
+            Sound::Sms => Some("Pop"),
+            Sound::Alarm => Some("Sosumi"),
+        };
+        let mut script = format!("display notification {:?} with title {:?}", body, title);
+        if let Some(name) = sound {
+            script.push_str(&format!(" sound name {:?}", name));
+        }
+        let status = std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .status()?;
+        if !status.success() {
+            return Err(format!("osascript exited with {}", status).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Notification for PlatformNotification {
+    fn show(&self, title: &str, body: &str) -> Result<(), Box<dyn Error>> {
+        // Names from the freedesktop.org sound naming specification.
+        let sound = match self.sound {
+            Sound::None => None,
+            Sound::Default => Some("bell"),
+            Sound::Reminder => Some("complete"),
+            Sound::Sms => Some("message-new-instant"),
+            Sound::Alarm => Some("alarm-clock-elapsed"),
+        };
+        let mut notification = notify_rust::Notification::new();
+        notification.summary(title).body(body).appname("pom");
+        if let Some(name) = sound {
+            notification.sound_name(name);
+        }
+        if self.duration == NotificationDuration::Long {
+            notification.timeout(notify_rust::Timeout::Never);
+        }
+        notification.show()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+//! Where the state, log, history and config files live.
+
+use clap::ArgMatches;
+use directories::ProjectDirs;
+use pom::PomError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LOGFILE_NAME: &str = "pomodoros.log";
+pub const STATEFILE_NAME: &str = ".rusty_pom";
+pub const HISTORYFILE_NAME: &str = "history.jsonl";
+pub const SOCKET_NAME: &str = "pom.sock";
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("com.github", "piepero", "rusty_pom")
+}
+
+/// The per-user directory for state and logs, `None` if it can't be determined.
+///
+/// That's `$XDG_STATE_HOME/rusty_pom` on Linux, Application Support on macOS and the local
+/// rather than the roaming app data on Windows, the state belongs to this machine.
+fn default_dir() -> Option<PathBuf> {
+    let dirs = project_dirs()?;
+    Some(
+        dirs.state_dir()
+            .unwrap_or_else(|| dirs.data_local_dir())
+            .to_path_buf(),
+    )
+}
+
+/// The per-user configuration file, `None` if it can't be determined.
+///
+/// With a `data_dir` it is looked for there, like all other files.
+pub fn config_file(data_dir: Option<&str>) -> Option<PathBuf> {
+    if let Some(dir) = data_dir {
+        return Some(Path::new(dir).join("config.toml"));
+    }
+    project_dirs().map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+/// The name of the timer, `pom resume coding` is short for `pom resume --name coding`.
+pub fn timer_name(matches: &ArgMatches) -> Option<&str> {
+    matches
+        .value_of("session")
+        .or_else(|| matches.value_of("name"))
+}
+
+/// The state file of the timer, from `--state-file` or `--data-dir`, see `resolve`.
+pub fn state_path(matches: &ArgMatches) -> Result<PathBuf, PomError> {
+    resolve(
+        matches.value_of("state-file"),
+        matches.value_of("data-dir"),
+        &named(STATEFILE_NAME, timer_name(matches)),
+    )
+}
+
+/// The log file of the timer, from `--log-file` or `--data-dir`, see `resolve`.
+pub fn log_path(matches: &ArgMatches) -> Result<PathBuf, PomError> {
+    resolve(
+        matches.value_of("log-file"),
+        matches.value_of("data-dir"),
+        &named(LOGFILE_NAME, timer_name(matches)),
+    )
+}
+
+/// The history file all timers share, from `--history-file` or `--data-dir`, see `resolve`.
+pub fn history_path(matches: &ArgMatches) -> Result<PathBuf, PomError> {
+    resolve(
+        matches.value_of("history-file"),
+        matches.value_of("data-dir"),
+        HISTORYFILE_NAME,
+    )
+}
+
+/// The socket of `--ipc`, next to the state file.
+pub fn socket_path(state_path: &Path, matches: &ArgMatches) -> PathBuf {
+    state_path.with_file_name(named(SOCKET_NAME, timer_name(matches)))
+}
+
+/// Namespace `file_name` for the timer called `name`, `pomodoros.log` becomes
+/// `pomodoros-coding.log` and `.rusty_pom` becomes `.rusty_pom-coding`.
+fn named(file_name: &str, name: Option<&str>) -> String {
+    let name = match name {
+        Some(name) => name,
+        None => return file_name.to_string(),
+    };
+    let path = Path::new(file_name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(extension)) => format!(
+            "{}-{}.{}",
+            stem.to_string_lossy(),
+            name,
+            extension.to_string_lossy()
+        ),
+        _ => format!("{}-{}", file_name, name),
+    }
+}
+
+/// Resolve the path of `file_name`.
+///
+/// An explicit path wins, then a file in `data_dir`. Otherwise a file of that name in the
+/// current directory is kept for backward compatibility, and new files go into the
+/// per-user directory.
+fn resolve(
+    explicit: Option<&str>,
+    data_dir: Option<&str>,
+    file_name: &str,
+) -> Result<PathBuf, PomError> {
+    if let Some(path) = explicit {
+        return Ok(PathBuf::from(path));
+    }
+    if let Some(dir) = data_dir {
+        fs::create_dir_all(dir)?;
+        return Ok(Path::new(dir).join(file_name));
+    }
+    if Path::new(file_name).exists() {
+        return Ok(PathBuf::from(file_name));
+    }
+    match default_dir() {
+        Some(dir) => {
+            fs::create_dir_all(&dir)?;
+            Ok(dir.join(file_name))
+        }
+        None => Ok(PathBuf::from(file_name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(args: &[&str]) -> ArgMatches {
+        let matches = crate::app().try_get_matches_from(args).unwrap();
+        match matches.subcommand() {
+            Some((_, sub_matches)) => sub_matches.clone(),
+            None => matches,
+        }
+    }
+
+    #[test]
+    fn names_go_before_the_extension() {
+        assert_eq!(named(LOGFILE_NAME, None), "pomodoros.log");
+        assert_eq!(named(LOGFILE_NAME, Some("coding")), "pomodoros-coding.log");
+        assert_eq!(named(STATEFILE_NAME, Some("coding")), ".rusty_pom-coding");
+        assert_eq!(named(SOCKET_NAME, Some("coding")), "pom-coding.sock");
+    }
+
+    #[test]
+    fn the_data_dir_holds_all_files_of_the_named_timer() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("pom");
+        let data = data_dir.to_str().unwrap();
+
+        let matches = matches(&["pom", "start", "--data-dir", data, "--name", "coding"]);
+
+        let state = state_path(&matches).unwrap();
+        assert_eq!(state, data_dir.join(".rusty_pom-coding"));
+        assert_eq!(
+            log_path(&matches).unwrap(),
+            data_dir.join("pomodoros-coding.log")
+        );
+        // All timers share the history.
+        assert_eq!(
+            history_path(&matches).unwrap(),
+            data_dir.join("history.jsonl")
+        );
+        assert_eq!(
+            socket_path(&state, &matches),
+            data_dir.join("pom-coding.sock")
+        );
+        assert_eq!(config_file(Some(data)), Some(data_dir.join("config.toml")));
+        assert!(data_dir.is_dir());
+    }
+
+    #[test]
+    fn explicit_files_win_over_the_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().to_str().unwrap();
+
+        let matches = matches(&[
+            "pom",
+            "--data-dir",
+            data,
+            "--state-file",
+            "/tmp/state",
+            "--log-file",
+            "/tmp/log",
+            "--history-file",
+            "/tmp/history",
+        ]);
+
+        assert_eq!(state_path(&matches).unwrap(), PathBuf::from("/tmp/state"));
+        assert_eq!(log_path(&matches).unwrap(), PathBuf::from("/tmp/log"));
+        assert_eq!(
+            history_path(&matches).unwrap(),
+            PathBuf::from("/tmp/history")
+        );
+    }
+
+    #[test]
+    fn the_timer_to_resume_may_be_given_without_name() {
+        assert_eq!(
+            timer_name(&matches(&["pom", "resume", "coding"])),
+            Some("coding")
+        );
+        assert_eq!(
+            timer_name(&matches(&["pom", "resume", "--name", "coding"])),
+            Some("coding")
+        );
+        assert_eq!(timer_name(&matches(&["pom", "start"])), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn new_files_go_into_the_xdg_state_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("XDG_STATE_HOME", dir.path());
+
+        let path = resolve(None, None, "no-such-file-in-the-current-dir").unwrap();
+
+        assert_eq!(
+            path,
+            dir.path()
+                .join("rusty_pom")
+                .join("no-such-file-in-the-current-dir")
+        );
+        assert!(dir.path().join("rusty_pom").is_dir());
+    }
+}
@@ -0,0 +1,62 @@
+//! A tiny HTTP server a running pom answers `GET /status` on, for dashboards and curl.
+//!
+//! The answer is the same JSON as that of the status socket, `null` between phases.
+
+use crate::ipc::Status;
+use log::warn;
+use pom::PomError;
+use std::sync::{Arc, Mutex};
+use tiny_http::{Header, Method, Response, Server};
+
+pub struct HttpStatusServer {
+    status: Arc<Mutex<Option<Status>>>,
+    server: Arc<Server>,
+}
+
+impl HttpStatusServer {
+    /// Listen on `address`, like `127.0.0.1:8080`, in a background thread.
+    pub fn start(address: &str) -> Result<HttpStatusServer, PomError> {
+        let server = Server::http(address).map_err(|e| {
+            PomError::InvalidArgument(format!("unable to listen on {}: {}", address, e))
+        })?;
+        let server = Arc::new(server);
+        let status = Arc::new(Mutex::new(None));
+
+        let thread_server = server.clone();
+        let thread_status = status.clone();
+        std::thread::spawn(move || {
+            for request in thread_server.incoming_requests() {
+                let response = if request.method() == &Method::Get && request.url() == "/status" {
+                    let body = match thread_status.lock() {
+                        Ok(status) => serde_json::to_string(&*status).unwrap_or_default(),
+                        Err(_) => continue,
+                    };
+                    let json = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                        .expect("the header is valid");
+                    Response::from_string(body).with_header(json)
+                } else {
+                    Response::from_string("not found").with_status_code(404)
+                };
+                if let Err(e) = request.respond(response) {
+                    warn!("Unable to answer status request: {}", e);
+                }
+            }
+        });
+
+        Ok(HttpStatusServer { status, server })
+    }
+
+    /// Publish the status of the running timer, `None` while no timer runs.
+    pub fn update(&self, status: Option<Status>) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+}
+
+impl Drop for HttpStatusServer {
+    fn drop(&mut self) {
+        // Ends the loop over the incoming requests.
+        self.server.unblock();
+    }
+}
@@ -0,0 +1,294 @@
+//! The state file that lets an interrupted pomodoro be continued later.
+
+use crate::error::PomError;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// The kind of session a timer is counting down.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Default for Phase {
+    fn default() -> Self {
+        Phase::Work
+    }
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Work => "Pomodoro",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+
+    pub fn is_break(self) -> bool {
+        self != Phase::Work
+    }
+}
+
+/// The shape of the state file written by this version of pom.
+///
+/// Version 1 is every file written before the version field existed.
+pub const STATE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SavedState {
+    /// Set to `STATE_VERSION` by `save_state`.
+    #[serde(default = "legacy_version")]
+    pub version: u32,
+    pub seconds_remaining: u64,
+    #[serde(default)]
+    pub phase: Phase,
+    #[serde(default)]
+    pub pomodoros_completed: u32,
+    #[serde(default)]
+    pub set_index: u32,
+    #[serde(default)]
+    pub set_size: u32,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Pomodoros finished on `completed_on`.
+    #[serde(default)]
+    pub completed_today: u32,
+    #[serde(default)]
+    pub completed_on: Option<NaiveDate>,
+    /// Days in a row with a finished pomodoro, up to `completed_on`.
+    #[serde(default)]
+    pub streak: u32,
+    #[serde(default)]
+    pub best_streak: u32,
+    /// The `--from-file` plan being worked through, `set_index` is the current task.
+    #[serde(default)]
+    pub task_file: Option<PathBuf>,
+    /// Focus time and interruptions of the pomodoros in the set so far.
+    #[serde(default)]
+    pub set_focus_seconds: u64,
+    #[serde(default)]
+    pub set_interruptions: u32,
+    /// Length of a pomodoro in this run, so `--restart` keeps it.
+    #[serde(default)]
+    pub planned_seconds: u64,
+    /// When the state was last saved, so an old interruption can be told apart.
+    #[serde(default)]
+    pub saved_at: Option<DateTime<Local>>,
+}
+
+impl SavedState {
+    /// The streak as of `today`, it is broken once a whole day passed without a pomodoro.
+    pub fn current_streak(&self, today: NaiveDate) -> u32 {
+        match self.completed_on {
+            Some(day) if day == today || day == today.pred() => self.streak,
+            _ => 0,
+        }
+    }
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+/// Read the state of the last run, a missing state file means there is none.
+///
+/// Files of older versions are migrated, a file of a newer pom can't be understood and is
+/// ignored with a warning.
+pub fn get_saved_state(path: &Path) -> Result<SavedState, PomError> {
+    Ok(read_state(path)?.unwrap_or_default())
+}
+
+/// Like `get_saved_state`, but `None` for the file of a newer pom.
+fn read_state(path: &Path) -> Result<Option<SavedState>, PomError> {
+    let input = match File::open(path) {
+        Ok(input) => input,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Some(SavedState::default())),
+        Err(e) => return Err(e.into()),
+    };
+    // Look at the version first, a newer file may not have the fields we expect.
+    let value: serde_json::Value = serde_json::from_reader(input)?;
+    let version = value
+        .get("version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or_else(|| legacy_version().into());
+    if version > STATE_VERSION.into() {
+        log::warn!(
+            "Ignoring {}, it was written by a newer pom (version {})",
+            path.display(),
+            version
+        );
+        return Ok(None);
+    }
+    // Version 1 gained its fields one by one, the missing ones take their defaults.
+    let mut state: SavedState = serde_json::from_value(value)?;
+    state.version = STATE_VERSION;
+    Ok(Some(state))
+}
+
+/// Write `state` in one rename, a crash in the middle of a checkpoint leaves the last one.
+pub fn save_state(path: &Path, state: &SavedState) -> Result<(), PomError> {
+    let mut value = serde_json::to_value(state)?;
+    value["version"] = STATE_VERSION.into();
+    write_atomically(path, &serde_json::to_string(&value)?)?;
+    Ok(())
+}
+
+/// Replace the file at `path` in one rename, so readers never see it half written.
+pub fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Update the remaining seconds in the state file, keeping everything else.
+///
+/// A corrupt file is an error rather than replaced, the file of a newer pom is left alone.
+pub fn save_remaining(path: &Path, seconds_remaining: u64) -> Result<(), PomError> {
+    let mut state = match read_state(path)? {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+    state.seconds_remaining = seconds_remaining;
+    state.saved_at = Some(Local::now());
+    save_state(path, &state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let state = SavedState {
+            seconds_remaining: 754,
+            phase: Phase::ShortBreak,
+            label: Some("write report".to_string()),
+            ..SavedState::default()
+        };
+
+        save_state(&path, &state).unwrap();
+        let loaded = get_saved_state(&path).unwrap();
+
+        assert_eq!(loaded.seconds_remaining, 754);
+        assert_eq!(loaded.phase, Phase::ShortBreak);
+        assert_eq!(loaded.label.as_deref(), Some("write report"));
+    }
+
+    #[test]
+    fn missing_file_means_no_saved_state() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let loaded = get_saved_state(&dir.path().join(".rusty_pom")).unwrap();
+
+        assert_eq!(loaded.seconds_remaining, 0);
+    }
+
+    #[test]
+    fn corrupt_file_is_a_state_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        std::fs::write(&path, "{\"seconds_remaining\": ").unwrap();
+
+        assert!(matches!(get_saved_state(&path), Err(PomError::State(_))));
+    }
+
+    #[test]
+    fn unversioned_file_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        std::fs::write(&path, "{\"seconds_remaining\": 300}").unwrap();
+
+        let loaded = get_saved_state(&path).unwrap();
+
+        assert_eq!(loaded.version, STATE_VERSION);
+        assert_eq!(loaded.seconds_remaining, 300);
+        assert_eq!(loaded.phase, Phase::Work);
+    }
+
+    #[test]
+    fn newer_version_means_no_saved_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let state = format!(
+            "{{\"version\": {}, \"seconds_remaining\": 300, \"mood\": \"happy\"}}",
+            STATE_VERSION + 1
+        );
+        std::fs::write(&path, state).unwrap();
+
+        let loaded = get_saved_state(&path).unwrap();
+
+        assert_eq!(loaded.seconds_remaining, 0);
+    }
+
+    #[test]
+    fn streak_breaks_after_a_day_off() {
+        let state = SavedState {
+            completed_on: Some(NaiveDate::from_ymd(2020, 10, 5)),
+            streak: 3,
+            best_streak: 7,
+            ..SavedState::default()
+        };
+
+        assert_eq!(state.current_streak(NaiveDate::from_ymd(2020, 10, 5)), 3);
+        assert_eq!(state.current_streak(NaiveDate::from_ymd(2020, 10, 6)), 3);
+        assert_eq!(state.current_streak(NaiveDate::from_ymd(2020, 10, 7)), 0);
+    }
+
+    #[test]
+    fn save_remaining_keeps_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let state = SavedState {
+            seconds_remaining: 1500,
+            set_index: 2,
+            set_size: 4,
+            ..SavedState::default()
+        };
+        save_state(&path, &state).unwrap();
+
+        save_remaining(&path, 60).unwrap();
+        let loaded = get_saved_state(&path).unwrap();
+
+        assert_eq!(loaded.seconds_remaining, 60);
+        assert_eq!(loaded.set_index, 2);
+        assert_eq!(loaded.set_size, 4);
+    }
+
+    #[test]
+    fn save_remaining_does_not_replace_a_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        std::fs::write(&path, "{\"seconds_remaining\": ").unwrap();
+
+        assert!(matches!(save_remaining(&path, 60), Err(PomError::State(_))));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"seconds_remaining\": "
+        );
+    }
+
+    #[test]
+    fn save_remaining_leaves_a_newer_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".rusty_pom");
+        let state = format!(
+            "{{\"version\": {}, \"seconds_remaining\": 300}}",
+            STATE_VERSION + 1
+        );
+        std::fs::write(&path, &state).unwrap();
+
+        save_remaining(&path, 60).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), state);
+    }
+}
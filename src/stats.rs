@@ -0,0 +1,269 @@
+//! Tagged log lines and the statistics computed from them.
+//!
+//! Every line starts with a tag like `[FINISHED]`, followed by the local time, the phase
+//! and the seconds counted down, e.g.
+//! `[FINISHED] 2020-10-05T09:25:00+02:00 phase=Work seconds=1500 Finished at 09:25:00`.
+//! Anything after the seconds is free text for humans.
+
+use crate::state::Phase;
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Started,
+    Finished,
+    Interrupted,
+    /// Ended early with `s`, it counts as finished everywhere.
+    Skipped,
+    /// The last pomodoro of a `--count` set finished, `seconds` is the focus time of the set.
+    SetComplete,
+}
+
+impl Event {
+    const ALL: &'static [Event] = &[
+        Event::Started,
+        Event::Finished,
+        Event::Interrupted,
+        Event::Skipped,
+        Event::SetComplete,
+    ];
+
+    fn tag(self) -> &'static str {
+        match self {
+            Event::Started => "[STARTED]",
+            Event::Finished => "[FINISHED]",
+            Event::Interrupted => "[INTERRUPTED]",
+            Event::Skipped => "[SKIPPED]",
+            Event::SetComplete => "[SET COMPLETE]",
+        }
+    }
+}
+
+/// One tagged line of the log file.
+#[derive(Debug)]
+pub struct LogEntry {
+    pub event: Event,
+    pub time: DateTime<Local>,
+    pub phase: Phase,
+    /// The planned duration for `Started`, the time spent otherwise.
+    pub seconds: u64,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} phase={:?} seconds={}",
+            self.event.tag(),
+            self.time.to_rfc3339(),
+            self.phase,
+            self.seconds
+        )
+    }
+}
+
+impl LogEntry {
+    pub fn now(event: Event, phase: Phase, seconds: u64) -> LogEntry {
+        LogEntry {
+            event,
+            time: Local::now(),
+            phase,
+            seconds,
+        }
+    }
+
+    /// Parse a log line, skipping whatever the logger put in front of the tag.
+    pub fn parse(line: &str) -> Option<LogEntry> {
+        // Tags may contain spaces, so look for them before splitting into fields.
+        let (start, event) = Event::ALL
+            .iter()
+            .filter_map(|&event| line.find(event.tag()).map(|start| (start, event)))
+            .min_by_key(|&(start, _)| start)?;
+        let mut fields = line[start + event.tag().len()..].split_whitespace();
+
+        let time = DateTime::parse_from_rfc3339(fields.next()?)
+            .ok()?
+            .with_timezone(&Local);
+        let phase = match fields.next()?.strip_prefix("phase=")? {
+            "Work" => Phase::Work,
+            "ShortBreak" => Phase::ShortBreak,
+            "LongBreak" => Phase::LongBreak,
+            _ => return None,
+        };
+        let seconds = fields.next()?.strip_prefix("seconds=")?.parse().ok()?;
+
+        Some(LogEntry {
+            event,
+            time,
+            phase,
+            seconds,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    pub completed_today: u32,
+    pub completed_this_week: u32,
+    pub focused_seconds: u64,
+    pub interruptions: u32,
+    pub sets_completed_this_week: u32,
+    /// Filled in from the state file, see `SavedState::current_streak`.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Filled in from the history, see `history::interruption_reasons`.
+    pub interruption_reasons: Vec<(String, u32)>,
+}
+
+impl Stats {
+    /// Sum up the work phases in `entries` relative to `today`.
+    pub fn from_entries(entries: impl IntoIterator<Item = LogEntry>, today: NaiveDate) -> Stats {
+        let mut stats = Stats::default();
+
+        for entry in entries.into_iter().filter(|e| e.phase == Phase::Work) {
+            let date = entry.time.naive_local().date();
+            match entry.event {
+                Event::Started => {}
+                // A skipped pomodoro counts as finished, like in the history.
+                Event::Finished | Event::Skipped => {
+                    stats.focused_seconds += entry.seconds;
+                    if date == today {
+                        stats.completed_today += 1;
+                    }
+                    if date.iso_week() == today.iso_week() {
+                        stats.completed_this_week += 1;
+                    }
+                }
+                Event::Interrupted => {
+                    stats.focused_seconds += entry.seconds;
+                    stats.interruptions += 1;
+                }
+                Event::SetComplete => {
+                    if date.iso_week() == today.iso_week() {
+                        stats.sets_completed_this_week += 1;
+                    }
+                }
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{self, SessionRecord};
+    use chrono::TimeZone;
+
+    #[test]
+    fn a_skipped_pomodoro_counts_in_the_stats_like_in_the_history() {
+        let time = Local.ymd(2020, 10, 5).and_hms(9, 25, 0);
+        let line = LogEntry {
+            event: Event::Skipped,
+            time,
+            phase: Phase::Work,
+            seconds: 1500,
+        }
+        .to_string();
+        let record = SessionRecord {
+            start: time - chrono::Duration::seconds(1500),
+            end: time,
+            planned_seconds: 1500,
+            actual_seconds: 1500,
+            completed: true,
+            skipped: true,
+            label: None,
+            reason: None,
+            note: None,
+        };
+
+        let stats = Stats::from_entries(LogEntry::parse(&line), time.naive_local().date());
+        let totals = history::totals_by_label(&[record]);
+
+        assert_eq!(stats.completed_today, 1);
+        assert_eq!(stats.completed_this_week, 1);
+        assert_eq!(stats.focused_seconds, 1500);
+        assert_eq!(totals[0].pomodoros, stats.completed_today);
+    }
+
+    fn entry(event: Event, time: DateTime<Local>, seconds: u64) -> LogEntry {
+        LogEntry {
+            event,
+            time,
+            phase: Phase::Work,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn every_tag_survives_a_round_trip() {
+        let time = Local.ymd(2020, 10, 5).and_hms(9, 25, 0);
+        for &event in Event::ALL {
+            let line = format!("[INFO] {} Finished", entry(event, time, 1500));
+
+            let parsed = LogEntry::parse(&line).unwrap();
+
+            assert_eq!(parsed.event, event);
+            assert_eq!(parsed.time, time);
+            assert_eq!(parsed.phase, Phase::Work);
+            assert_eq!(parsed.seconds, 1500);
+        }
+    }
+
+    #[test]
+    fn lines_without_a_tag_are_ignored() {
+        // Lines of pom versions before the tags, and other log messages.
+        assert!(LogEntry::parse("[INFO] Finished at 09:25:00").is_none());
+        assert!(LogEntry::parse("[INFO] Starting pomodoro 1 of 4").is_none());
+        assert!(LogEntry::parse("").is_none());
+        assert!(LogEntry::parse("[FINISHED] yesterday phase=Work seconds=1500").is_none());
+        assert!(
+            LogEntry::parse("[FINISHED] 2020-10-05T09:25:00+02:00 phase=Nap seconds=1").is_none()
+        );
+        assert!(LogEntry::parse("[FINISHED] 2020-10-05T09:25:00+02:00 phase=Work").is_none());
+    }
+
+    #[test]
+    fn the_first_tag_of_a_line_counts() {
+        let line = "[INTERRUPTED] 2020-10-05T09:25:00+02:00 phase=ShortBreak seconds=60 \
+                    [FINISHED] in the free text";
+
+        let parsed = LogEntry::parse(line).unwrap();
+
+        assert_eq!(parsed.event, Event::Interrupted);
+        assert_eq!(parsed.phase, Phase::ShortBreak);
+    }
+
+    #[test]
+    fn pomodoros_count_for_today_and_the_iso_week() {
+        // A Wednesday, the week started on Monday the 5th.
+        let today = NaiveDate::from_ymd(2020, 10, 7);
+        let at = |day, hour| Local.ymd(2020, 10, day).and_hms(hour, 0, 0);
+        let entries = vec![
+            entry(Event::Started, at(7, 9), 1500),
+            entry(Event::Finished, at(7, 9), 1500),
+            entry(Event::Finished, at(7, 23), 1500),
+            entry(Event::Finished, at(6, 23), 1500),
+            entry(Event::Finished, at(5, 0), 1500),
+            // Sunday, last week.
+            entry(Event::Finished, at(4, 23), 1500),
+            entry(Event::Interrupted, at(7, 10), 600),
+            entry(Event::SetComplete, at(7, 11), 3000),
+            entry(Event::SetComplete, at(4, 11), 3000),
+            LogEntry {
+                phase: Phase::ShortBreak,
+                ..entry(Event::Finished, at(7, 10), 300)
+            },
+        ];
+
+        let stats = Stats::from_entries(entries, today);
+
+        assert_eq!(stats.completed_today, 2);
+        assert_eq!(stats.completed_this_week, 4);
+        assert_eq!(stats.focused_seconds, 5 * 1500 + 600);
+        assert_eq!(stats.interruptions, 1);
+        assert_eq!(stats.sets_completed_this_week, 1);
+    }
+}
@@ -0,0 +1,130 @@
+//! A plan of labeled pomodoros, one task per line.
+//!
+//! A line is either `minutes: label` or just `label` for a pomodoro of the default
+//! length. Blank lines and lines starting with `#` are skipped.
+
+use crate::error::PomError;
+use std::fs;
+use std::path::Path;
+
+/// A task takes at most a day.
+const MAX_MINUTES: u64 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    /// Length of the pomodoro, `None` for the default.
+    pub minutes: Option<u64>,
+    pub label: String,
+}
+
+impl Task {
+    fn parse(line: &str) -> Option<Task> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        if let Some(colon) = line.find(':') {
+            if let Ok(minutes) = line[..colon].trim().parse() {
+                return Some(Task {
+                    minutes: Some(minutes),
+                    label: line[colon + 1..].trim().to_string(),
+                });
+            }
+        }
+        Some(Task {
+            minutes: None,
+            label: line.to_string(),
+        })
+    }
+}
+
+/// Read the tasks in `path`, a file without any is an error.
+pub fn load(path: &Path) -> Result<Vec<Task>, PomError> {
+    let tasks: Vec<Task> = fs::read_to_string(path)?
+        .lines()
+        .filter_map(Task::parse)
+        .collect();
+    if tasks.is_empty() {
+        return Err(PomError::InvalidArgument(format!(
+            "no tasks in {}",
+            path.display()
+        )));
+    }
+    if let Some(task) = tasks
+        .iter()
+        .find(|task| task.minutes.map_or(false, |m| m == 0 || m > MAX_MINUTES))
+    {
+        return Err(PomError::InvalidArgument(format!(
+            "task '{}' in {} must take between 1 and {} minutes",
+            task.label,
+            path.display(),
+            MAX_MINUTES
+        )));
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_text(text: &str) -> Result<Vec<Task>, PomError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.txt");
+        fs::write(&path, text).unwrap();
+        load(&path)
+    }
+
+    fn task(minutes: Option<u64>, label: &str) -> Task {
+        Task {
+            minutes,
+            label: label.to_string(),
+        }
+    }
+
+    #[test]
+    fn blank_lines_and_comments_are_skipped() {
+        let tasks = load_text("# Monday\n\nWrite the report\n   \n  # later\nReview PR\n").unwrap();
+
+        assert_eq!(
+            tasks,
+            vec![task(None, "Write the report"), task(None, "Review PR")]
+        );
+    }
+
+    #[test]
+    fn tasks_may_have_their_own_length() {
+        let tasks = load_text("50: Deep work\n 10 :Inbox\nRead\n").unwrap();
+
+        assert_eq!(
+            tasks,
+            vec![
+                task(Some(50), "Deep work"),
+                task(Some(10), "Inbox"),
+                task(None, "Read")
+            ]
+        );
+    }
+
+    #[test]
+    fn a_prefix_that_is_no_number_belongs_to_the_label() {
+        let tasks = load_text("Fix: the login bug\n1.5: Stretch\n").unwrap();
+
+        assert_eq!(
+            tasks,
+            vec![task(None, "Fix: the login bug"), task(None, "1.5: Stretch")]
+        );
+    }
+
+    #[test]
+    fn lengths_outside_a_day_are_refused() {
+        assert!(load_text("0: Nothing\n").is_err());
+        assert!(load_text("1441: Too much\n").is_err());
+        assert!(load_text("1440: A whole day\n").is_ok());
+    }
+
+    #[test]
+    fn a_file_without_tasks_is_an_error() {
+        assert!(load_text("# just a comment\n\n").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! A ticking clock for ambient focus, behind the `tick-sound` feature.
+
+#[cfg(feature = "tick-sound")]
+pub use self::audio::Ticker;
+#[cfg(not(feature = "tick-sound"))]
+pub use self::silent::Ticker;
+
+#[cfg(feature = "tick-sound")]
+mod audio {
+    use log::warn;
+    use pom::PomError;
+    use rodio::source::{SineWave, Source};
+    use rodio::{Decoder, OutputStream, OutputStreamHandle};
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::Path;
+    use std::time::Duration;
+
+    /// Plays a short tick on the default audio output.
+    pub struct Ticker {
+        // Dropping the stream stops all sound, so it has to live as long as the ticker.
+        _stream: OutputStream,
+        handle: OutputStreamHandle,
+        /// The contents of the sound file, `None` for the built-in click.
+        sound: Option<Vec<u8>>,
+    }
+
+    impl Ticker {
+        pub fn new(sound_file: Option<&Path>) -> Result<Ticker, PomError> {
+            let sound = match sound_file {
+                Some(path) => {
+                    let sound = fs::read(path)?;
+                    Decoder::new(Cursor::new(sound.clone())).map_err(|e| {
+                        PomError::InvalidArgument(format!(
+                            "unable to play {}: {}",
+                            path.display(),
+                            e
+                        ))
+                    })?;
+                    Some(sound)
+                }
+                None => None,
+            };
+            let (stream, handle) = OutputStream::try_default().map_err(|e| {
+                PomError::InvalidArgument(format!("unable to open the audio output: {}", e))
+            })?;
+            Ok(Ticker {
+                _stream: stream,
+                handle,
+                sound,
+            })
+        }
+
+        /// Start playing the tick without waiting for it to end.
+        pub fn tick(&self) {
+            let played = match &self.sound {
+                Some(sound) => Decoder::new(Cursor::new(sound.clone()))
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| {
+                        self.handle
+                            .play_raw(source.convert_samples())
+                            .map_err(|e| e.to_string())
+                    }),
+                None => self
+                    .handle
+                    .play_raw(
+                        SineWave::new(1000)
+                            .take_duration(Duration::from_millis(15))
+                            .amplify(0.2),
+                    )
+                    .map_err(|e| e.to_string()),
+            };
+            if let Err(e) = played {
+                warn!("Unable to play tick: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "tick-sound"))]
+mod silent {
+    use pom::PomError;
+    use std::path::Path;
+
+    /// Stand-in for builds without audio support.
+    pub struct Ticker;
+
+    impl Ticker {
+        pub fn new(_sound_file: Option<&Path>) -> Result<Ticker, PomError> {
+            Err(PomError::InvalidArgument(
+                "--tick-sound needs pom built with the tick-sound feature".to_string(),
+            ))
+        }
+
+        pub fn tick(&self) {}
+    }
+}
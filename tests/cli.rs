@@ -0,0 +1,186 @@
+//! End-to-end runs of the `pom` binary, sped up with `--test-scale` to take a few seconds.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::Path;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// The arguments every run gets: no breaks afterwards and no notifications or sounds, with
+/// all files kept in `dir`.
+fn quiet_args(dir: &Path) -> Vec<String> {
+    [
+        "--break-short",
+        "0",
+        "--break-long",
+        "0",
+        "--no-notify",
+        "--no-bell",
+        "--sound",
+        "none",
+        "--data-dir",
+        dir.to_str().unwrap(),
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect()
+}
+
+fn saved_state(dir: &Path) -> serde_json::Value {
+    let state = std::fs::read_to_string(dir.join(".rusty_pom")).unwrap();
+    serde_json::from_str(&state).unwrap()
+}
+
+#[test]
+fn short_pomodoro_runs_to_the_end() {
+    let dir = TempDir::new().unwrap();
+
+    Command::cargo_bin("pom")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(&["start", "-d", "1m", "--test-scale", "30"])
+        .args(quiet_args(dir.path()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Finished at"));
+
+    assert_eq!(saved_state(dir.path())["seconds_remaining"], 0);
+}
+
+/// Run pom with `args` for two seconds, then stop it with SIGINT like Ctrl-C would.
+#[cfg(unix)]
+fn interrupt(dir: &Path, args: &[&str]) {
+    let mut pom = std::process::Command::new(assert_cmd::cargo::cargo_bin("pom"))
+        .current_dir(dir)
+        .args(args)
+        .args(quiet_args(dir))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_secs(2));
+    let kill = std::process::Command::new("kill")
+        .args(&["-INT", &pom.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(kill.success());
+    let status = pom.wait().unwrap();
+
+    assert!(!status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_saves_the_remaining_time_and_label() {
+    let dir = TempDir::new().unwrap();
+
+    interrupt(
+        dir.path(),
+        &[
+            "start",
+            "-d",
+            "10m",
+            "--test-scale",
+            "60",
+            "-l",
+            "write report",
+        ],
+    );
+
+    let state = saved_state(dir.path());
+    let remaining = state["seconds_remaining"].as_u64().unwrap();
+    assert!(
+        remaining > 0 && remaining < 600,
+        "{} seconds remaining",
+        remaining
+    );
+    assert_eq!(state["label"], "write report");
+}
+
+#[cfg(unix)]
+#[test]
+fn label_given_on_resume_is_kept_when_interrupted_again() {
+    let dir = TempDir::new().unwrap();
+
+    interrupt(
+        dir.path(),
+        &["start", "-d", "10m", "--test-scale", "60", "-l", "draft"],
+    );
+    assert_eq!(saved_state(dir.path())["label"], "draft");
+    interrupt(
+        dir.path(),
+        &["resume", "--test-scale", "60", "-l", "final version"],
+    );
+    assert_eq!(saved_state(dir.path())["label"], "final version");
+
+    Command::cargo_bin("pom")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(&["resume", "--test-scale", "600"])
+        .args(quiet_args(dir.path()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("'final version'"));
+}
+
+#[test]
+fn test_notify_only_shows_the_notification() {
+    let dir = TempDir::new().unwrap();
+
+    // Showing it fails without a desktop, but it must not start a session either way.
+    Command::cargo_bin("pom")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(&["test-notify", "--sound", "none", "--ipc"])
+        .args(&["--data-dir", dir.path().to_str().unwrap()])
+        .assert();
+
+    assert!(!dir.path().join("pomodoros.log").exists());
+    assert!(!dir.path().join("pom.sock").exists());
+}
+
+#[test]
+fn block_refuses_hosts_that_would_add_lines_to_the_hosts_file() {
+    let dir = TempDir::new().unwrap();
+
+    Command::cargo_bin("pom")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(&["start", "--block", "example.com,evil.com\n1.2.3.4 bank.com"])
+        .args(quiet_args(dir.path()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid host"));
+}
+
+#[cfg(unix)]
+#[test]
+fn bare_pom_starts_over_like_start() {
+    let dir = TempDir::new().unwrap();
+
+    interrupt(
+        dir.path(),
+        &["start", "-d", "10m", "--test-scale", "60", "-l", "draft"],
+    );
+    assert!(
+        saved_state(dir.path())["seconds_remaining"]
+            .as_u64()
+            .unwrap()
+            > 0
+    );
+
+    Command::cargo_bin("pom")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(&["-d", "1m", "--test-scale", "30"])
+        .args(quiet_args(dir.path()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1m of 1m"))
+        .stdout(predicate::str::contains("draft").not());
+
+    assert_eq!(saved_state(dir.path())["seconds_remaining"], 0);
+}